@@ -6,14 +6,17 @@ mod mods;
 
 // std library
 use std::path::{Path, PathBuf};
-use std::{thread, time};
+use std::process;
 
 // 3rd party crates
+use clap::Parser;
 use dirs::home_dir;
 
 // imports
 use mods::{
-    block::Block,
+    amount::Amount,
+    cli::{self, Cli},
+    config::Config,
     file::FileOps,
     helpers::{create_transaction, create_wallet, mine_block, verify_chain},
     log::{Log, LogLevel},
@@ -39,11 +42,19 @@ lazy_static! {
     static ref DATA_PATH: PathBuf = HOME.as_path().join(".mockchain").join("data");
 }
 
+#[cfg(not(test))]
 lazy_static! {
     #[derive(Debug)]
     static ref LOG_PATH: PathBuf = HOME.as_path().join(".mockchain").join("log");
 }
 
+// tests log under the temp directory so they never write to the users log
+#[cfg(test)]
+lazy_static! {
+    #[derive(Debug)]
+    static ref LOG_PATH: PathBuf = std::env::temp_dir().join("mockchain_test_log");
+}
+
 lazy_static! {
     #[derive(Debug)]
     static ref BLOCKCHAIN_PATH: PathBuf = DATA_PATH.as_path().join("blockchain.json");
@@ -75,42 +86,50 @@ lazy_static! {
 }
 
 fn main() {
-    if !Path::new(BLOCKCHAIN_PATH.as_path()).exists() {
+    let cli = Cli::parse();
+    let config = Config::load(cli.data_dir);
+
+    if !config.blockchain_path.exists() {
         Log::init();
 
         Log::new(LogLevel::INFO, 1, None);
 
-        FileOps::init(false);
+        FileOps::init(&config, false);
 
         Log::new(LogLevel::INFO, 2, None);
-
-        // sleep to allow init
-        let half_sec = time::Duration::from_millis(500);
-        thread::sleep(half_sec);
-
-        Block::add_genesis_block();
-
         Log::new(LogLevel::INFO, 3, None);
     }
 
+    // a subcommand runs once and exits instead of starting the menu
+    if let Some(command) = cli.command {
+        match cli::dispatch(command, &config) {
+            Ok(output) => display_msg(Message::Success(output, None)),
+            Err(e) => {
+                display_msg(Message::Failure(e, None));
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     Repl::print_intro();
     Repl::print_options();
 
     loop {
         print!("Select an option: ");
-        let _ = match Repl::get_input() {
+        match Repl::get_input() {
             Some(choice) => match choice {
                 0 => Repl::print_options(),
-                1 => option1(),
-                2 => option2(),
-                3 => option3(),
-                4 => println!("\n{:#?}\n", FileOps::parse(BLOCKCHAIN_PATH.as_path())),
-                5 => println!("\n{:#?}\n", FileOps::parse(TRANSACTIONS_PATH.as_path())),
-                6 => println!("\n{:#?}\n", FileOps::parse(WALLETS_PATH.as_path())),
-                7 => println!("\n{:#?}\n", FileOps::parse(KEYPAIRS_PATH.as_path())),
-                8 => println!("\n{:#?}\n", FileOps::parse(SIGNING_DATA_PATH.as_path())),
-                9 => option9(),
-                10 => println!("VALID CHAIN: {}", verify_chain()),
+                1 => option1(&config),
+                2 => option2(&config),
+                3 => option3(&config),
+                4 => display_file(&config.blockchain_path),
+                5 => display_file(&config.transactions_path),
+                6 => display_file(&config.wallets_path),
+                7 => display_file(&config.keypairs_path),
+                8 => display_file(&config.signing_data_path),
+                9 => option9(&config),
+                10 => println!("VALID CHAIN: {}", verify_chain(&config)),
                 11 => {
                     println!("See you again soon! 👋 Your data files will be preserved 😃");
                     break;
@@ -130,11 +149,34 @@ fn main() {
 
 // Options helper functions
 
-fn option1() {
+fn display_file(path: &Path) {
+    match FileOps::parse(path) {
+        Ok(data) => println!("\n{:#?}\n", data),
+        Err(e) => display_msg(Message::Failure(
+            "Unable to read data file: {}".to_string(),
+            Some(vec![e.to_string()]),
+        )),
+    };
+}
+
+fn wallet_exists(config: &Config, name: &str) -> bool {
+    match Wallet::name_exists(&FileOps {}, &config.wallets_path, name) {
+        Ok(exists) => exists,
+        Err(e) => {
+            display_msg(Message::Failure(
+                "Unable to read wallets: {}".to_string(),
+                Some(vec![e.to_string()]),
+            ));
+            false
+        }
+    }
+}
+
+fn option1(config: &Config) {
     print!("Add a name for this wallet: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if Wallet::name_exists(&name) {
+            if wallet_exists(config, &name) {
                 display_msg(Message::Failure(
                     "Wallet with name '{}' already exists".to_string(),
                     Some(vec![name.clone()]),
@@ -145,43 +187,65 @@ fn option1() {
                 "Creating wallet for '{}'".to_string(),
                 Some(vec![name.clone()]),
             ));
-            create_wallet(name);
-            display_msg(Message::Success("Wallet created".to_string(), None));
+            match create_wallet(config, name) {
+                Ok(_) => display_msg(Message::Success("Wallet created".to_string(), None)),
+                Err(e) => display_msg(Message::Failure(
+                    "Wallet could not be created: {}".to_string(),
+                    Some(vec![e]),
+                )),
+            }
         }
         None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
 }
 
-fn option2() {
+fn option2(config: &Config) {
     print!("Name of account mining this block: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if !Wallet::name_exists(&name) {
+            if !wallet_exists(config, &name) {
                 display_msg(Message::Failure(
                     "No wallet found under name '{}'".to_string(),
                     Some(vec![name.clone()]),
                 ));
             } else {
-                mine_block(name.clone());
-                display_msg(Message::Success(
-                    "New block mined successfully. A reward transaction has been added for '{}'"
-                        .to_string(),
-                    Some(vec![name.clone()]),
-                ));
+                match mine_block(config, name.clone()) {
+                    Ok(_) => display_msg(Message::Success(
+                        "New block mined successfully. The block reward and fees have been paid to '{}'"
+                            .to_string(),
+                        Some(vec![name.clone()]),
+                    )),
+                    Err(e) => display_msg(Message::Failure(
+                        "Block could not be mined: {}".to_string(),
+                        Some(vec![e]),
+                    )),
+                }
             }
         }
         None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
 }
 
-fn option3() {
+fn option3(config: &Config) {
     let mut senders_name = String::new();
     let mut recipients_name = String::new();
-    let amount: i32;
+    let amount: i64;
+    let fee: u32;
+    // wallets.json is parsed once for the name and balance checks
+    let ledger = match FileOps::open(&config.wallets_path) {
+        Ok(ledger) => ledger,
+        Err(e) => {
+            display_msg(Message::Failure(
+                "Unable to read wallets: {}".to_string(),
+                Some(vec![e.to_string()]),
+            ));
+            return;
+        }
+    };
     print!("Name on senders wallet: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if !Wallet::name_exists(&name) {
+            if !ledger.name_exists(&name) {
                 display_msg(Message::Failure(
                     "No wallet found under name '{}'".to_string(),
                     Some(vec![name.clone()]),
@@ -193,9 +257,9 @@ fn option3() {
         None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
     print!("Name on recipients wallet: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if !Wallet::name_exists(&name) {
+            if !ledger.name_exists(&name) {
                 display_msg(Message::Failure(
                     "No wallet found under name '{}'".to_string(),
                     Some(vec![name.clone()]),
@@ -207,7 +271,7 @@ fn option3() {
         None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
     print!("Amount: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input() {
         Some(val) => {
             amount = val;
             if val <= 0 {
@@ -217,10 +281,36 @@ fn option3() {
                 ));
                 return;
             }
-            if Wallet::get_balance(&senders_name) < amount {
+            print!("Fee: ");
+            match Repl::get_input() {
+                Some(val) => fee = val,
+                None => {
+                    display_msg(Message::Failure(
+                        "Please enter a positive whole number".to_string(),
+                        None,
+                    ));
+                    return;
+                }
+            };
+            let balance = ledger.balance(&senders_name).unwrap_or(0);
+            let cost = match Amount::new(amount).and_then(|a| a.checked_add(Amount::from(fee))) {
+                Ok(cost) => cost.value(),
+                Err(e) => {
+                    display_msg(Message::Failure(
+                        "Invalid amount: {}".to_string(),
+                        Some(vec![e]),
+                    ));
+                    return;
+                }
+            };
+            if balance < cost {
                 display_msg(Message::Failure(
-                    "Not enough funds to send {} from {}'s account".to_string(),
-                    Some(vec![amount.to_string(), senders_name.clone()]),
+                    "Not enough funds to send {} plus a fee of {} from {}'s account".to_string(),
+                    Some(vec![
+                        amount.to_string(),
+                        fee.to_string(),
+                        senders_name.clone(),
+                    ]),
                 ));
                 return;
             }
@@ -228,18 +318,24 @@ fn option3() {
                 "Adding new pending transaction\n".to_string(),
                 None,
             ));
-            if let Some(key) = Wallet::get_wallet_address(&senders_name) {
+            if let Some(key) = ledger.address(&senders_name) {
                 println!("\tSenders public key: {}", key);
             }
-            if let Some(key) = Wallet::get_wallet_address(&recipients_name) {
+            if let Some(key) = ledger.address(&recipients_name) {
                 println!("\tRecipients public key: {}", key);
             }
-            println!("\tAmount: {}\n", &amount);
-            create_transaction(senders_name, recipients_name, amount);
-            display_msg(Message::Success(
-                "Transaction added successfully".to_string(),
-                None,
-            ));
+            println!("\tAmount: {}", &amount);
+            println!("\tFee: {}\n", &fee);
+            match create_transaction(config, senders_name, recipients_name, amount, fee, None) {
+                Ok(_) => display_msg(Message::Success(
+                    "Transaction added successfully".to_string(),
+                    None,
+                )),
+                Err(e) => display_msg(Message::Failure(
+                    "Transaction rejected: {}".to_string(),
+                    Some(vec![e]),
+                )),
+            }
         }
         None => display_msg(Message::Failure(
             "Please enter a positive whole number".to_string(),
@@ -248,13 +344,13 @@ fn option3() {
     };
 }
 
-fn option9() {
-    fn helper(preserve: bool) {
+fn option9(config: &Config) {
+    fn helper(config: &Config, preserve: bool) {
         display_msg(Message::Success(
             "Re-initialising blockchain...".to_string(),
             None,
         ));
-        FileOps::init(preserve);
+        FileOps::init(config, preserve);
         display_msg(Message::Success(
             "Blockchain init completed successfully".to_string(),
             None,
@@ -265,7 +361,6 @@ fn option9() {
         };
         display_msg(Message::Success(msg.to_string(), None));
         Log::new(LogLevel::WARNING, msg_key, None);
-        Block::add_genesis_block();
         Log::new(LogLevel::INFO, 3, None);
     }
 
@@ -286,10 +381,10 @@ fn option9() {
             };
             match keep.as_str() {
                 "y" => {
-                    helper(true);
+                    helper(config, true);
                 }
                 "n" => {
-                    helper(false);
+                    helper(config, false);
                 }
                 "Invalid option" => display_msg(Message::Failure(wipe, None)),
                 _ => display_msg(Message::Failure(wipe, None)),
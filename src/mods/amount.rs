@@ -0,0 +1,175 @@
+/*
+    Validated amount type for balances, transfers and
+    transaction values
+*/
+
+// std library
+use std::fmt;
+
+// 3rd party crates
+use serde::{Deserialize, Serialize};
+
+/// Defines a non-negative amount of tokens. Arithmetic
+/// is checked so an amount can never wrap or go below
+/// zero. It is stored in the data files as a plain
+/// integer
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// 0: i64 -> number of tokens, never negative
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord
+/// ```
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "i64", into = "i64")]
+pub struct Amount(i64);
+
+impl Amount {
+    /// Creates an Amount, rejecting negative values
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// value: i64 -> number of tokens
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Amount, String>
+    /// ```
+    pub fn new(value: i64) -> Result<Amount, String> {
+        if value < 0 {
+            return Err(format!("Amount must not be negative, found {}", value));
+        }
+        Ok(Amount(value))
+    }
+
+    /// Gets the number of tokens
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// i64
+    /// ```
+    pub fn value(self) -> i64 {
+        self.0
+    }
+
+    /// Adds two amounts, failing instead of overflowing
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// other: Amount -> amount to add
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Amount, String>
+    /// ```
+    pub fn checked_add(self, other: Amount) -> Result<Amount, String> {
+        match self.0.checked_add(other.0) {
+            Some(total) => Ok(Amount(total)),
+            None => Err(format!(
+                "Adding {} to {} overflows the largest possible amount",
+                other, self
+            )),
+        }
+    }
+
+    /// Subtracts an amount, failing if the result would
+    /// be negative
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// other: Amount -> amount to subtract
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Amount, String>
+    /// ```
+    pub fn checked_sub(self, other: Amount) -> Result<Amount, String> {
+        if other.0 > self.0 {
+            return Err(format!("Cannot subtract {} from {}", other, self));
+        }
+        Ok(Amount(self.0 - other.0))
+    }
+}
+
+impl TryFrom<i64> for Amount {
+    type Error = String;
+
+    fn try_from(value: i64) -> Result<Amount, String> {
+        Amount::new(value)
+    }
+}
+
+impl From<u32> for Amount {
+    fn from(value: u32) -> Amount {
+        Amount(value as i64)
+    }
+}
+
+impl From<Amount> for i64 {
+    fn from(amount: Amount) -> i64 {
+        amount.0
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_amount {
+    use super::*;
+
+    use serde_json::{from_value, json, to_value};
+
+    #[test]
+    fn test_negative_rejected() {
+        let negative = Amount::new(-1);
+        let parsed: Result<Amount, _> = from_value(json!(-5));
+
+        assert_eq!(
+            Err("Amount must not be negative, found -1".to_string()),
+            negative
+        );
+        assert!(parsed.is_err());
+        assert_eq!(Ok(Amount(0)), Amount::new(0));
+        assert_eq!(json!(7), to_value(Amount::new(7).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let max = Amount::new(i64::MAX - 1).unwrap();
+        let two = Amount::new(2).unwrap();
+
+        assert!(max.checked_add(two).unwrap_err().contains("overflows"));
+        assert_eq!(Ok(Amount(i64::MAX)), max.checked_add(Amount(1)));
+        assert_eq!(Ok(Amount(0)), two.checked_sub(two));
+        assert!(two.checked_sub(Amount::from(3u32)).is_err());
+    }
+}
@@ -1,15 +1,44 @@
+// std library
+use std::path::Path;
+
 // 3rd party crates
-use serde::Serialize;
-use serde_json::{to_string, to_value, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 // imports
 use super::{
-    crypto::{get_merkle_root, hash_block},
+    crypto::{hash_block_header_with, hash_transaction, HashAlgo},
     file::FileOps,
-    helpers::get_timestamp,
+    helpers::now_rfc3339,
     log::{Log, LogLevel},
+    merkle::merkle_root,
+    transaction::Transaction,
 };
-use crate::{BLOCKCHAIN_PATH, TRANSACTIONS_PATH};
+
+// number of leading zeros a mined block hash must have
+pub const DIFFICULTY: usize = 2;
+
+// bounds the adjusted difficulty is kept within
+pub const MIN_DIFFICULTY: usize = 1;
+pub const MAX_DIFFICULTY: usize = 4;
+
+// seconds the difficulty adjustment aims to leave between blocks
+pub const TARGET_BLOCK_TIME_SECS: u64 = 10;
+
+// number of recent blocks the difficulty adjustment looks at
+pub const DIFFICULTY_WINDOW: usize = 5;
+
+// tokens paid to the miner of a block before fees
+pub const BLOCK_REWARD: u32 = 50;
+
+// most mempool transactions mined into one block, not counting the coinbase
+pub const MAX_TXS_PER_BLOCK: usize = 100;
+
+// sender recorded on the coinbase transaction of a block
+pub const COINBASE_ADDRESS: &str = "COINBASE";
+
+// fixed creation time of the genesis block
+pub const GENESIS_TIMESTAMP: &str = "2024-01-01T00:00:00+00:00";
 
 /// Defines a block to append to the chain
 ///
@@ -25,9 +54,9 @@ use crate::{BLOCKCHAIN_PATH, TRANSACTIONS_PATH};
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Block {
     // num: u32,
     pub timestamp: String,
@@ -39,8 +68,35 @@ pub struct Block {
 }
 
 impl Block {
-    /// Creates and writes the genesis block to
-    /// the blockchain
+    /// Creates a new unmined Block holding the given
+    /// transactions with its merkle root computed
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// previous_hash: String    -> hash of the previous Block
+    /// transactions: Vec<Value> -> transactions to include
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Block
+    /// ```
+    pub fn new(previous_hash: String, transactions: Vec<Value>) -> Block {
+        Block {
+            timestamp: now_rfc3339(),
+            hash: String::new(),
+            previous_hash,
+            nonce: 0,
+            merkle_root: merkle_root(&transactions),
+            transactions: json!({ "transactions": transactions }),
+        }
+    }
+
+    /// Creates the genesis block. Every field is fixed
+    /// so all chains start from the same first block
     ///
     /// # Visibility
     /// public
@@ -49,43 +105,492 @@ impl Block {
     /// None
     ///
     /// # Returns
+    /// ```
+    /// Block
+    /// ```
+    pub fn genesis() -> Block {
+        let mut genesis_block = Block {
+            timestamp: String::from(GENESIS_TIMESTAMP),
+            hash: String::new(),
+            previous_hash: "0".repeat(64),
+            nonce: 0,
+            transactions: json!({ "transactions": [] }),
+            merkle_root: merkle_root(&[]),
+        };
+        genesis_block.hash = genesis_block.calculate_hash();
+        genesis_block
+    }
+
+    /// Reads a Block from untrusted JSON such as a hand
+    /// edited file or a message from a peer. The merkle
+    /// root and hash are recomputed so a block whose stored
+    /// values do not match its contents is rejected
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// value: &Value -> block JSON
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, String>
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Block, String> {
+        Block::from_value_with(value, HashAlgo::default())
+    }
+
+    /// Reads the block at position `index` of a chain from
+    /// untrusted JSON. The genesis block is always hashed
+    /// with SHA-256 so it is read with from_value, every
+    /// later block with the algorithm the chain uses
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// value: &Value  -> block JSON
+    /// index: usize   -> position of the block, 0 for genesis
+    /// algo: HashAlgo -> hash function of the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, String>
+    /// ```
+    pub fn from_chain_value(value: &Value, index: usize, algo: HashAlgo) -> Result<Block, String> {
+        if index == 0 {
+            Block::from_value(value)
+        } else {
+            Block::from_value_with(value, algo)
+        }
+    }
+
+    /// Reads a Block from untrusted JSON like from_value,
+    /// checking the hash with the algorithm the chain uses
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// value: &Value  -> block JSON
+    /// algo: HashAlgo -> hash function of the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, String>
+    /// ```
+    pub fn from_value_with(value: &Value, algo: HashAlgo) -> Result<Block, String> {
+        let block: Block =
+            serde_json::from_value(value.clone()).map_err(|e| format!("Malformed block: {}", e))?;
+        if block.merkle_root != merkle_root(&block.transaction_list()) {
+            return Err(format!(
+                "Block {} has a merkle root that does not match its transactions",
+                block.hash
+            ));
+        }
+        if block.hash != block.hash_with(algo) {
+            return Err(format!(
+                "Block {} has a hash that does not match its contents",
+                block.hash
+            ));
+        }
+        Ok(block)
+    }
+
+    /// Writes the genesis block to the blockchain if
+    /// the chain does not have any blocks yet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to blockchain.json
+    /// ```
+    ///
+    /// # Returns
     /// Nothing
-    pub fn add_genesis_block() {
-        let timestamp = get_timestamp();
-        let transactions = match to_string(&Value::Array([].to_vec())) {
-            Ok(val) => val,
+    pub fn add_genesis_block(path: &Path) {
+        let base_data = match FileOps::parse(path) {
+            Ok(data) => data,
             Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 1, Some(vec!["Value::String".to_string()]));
-                panic!(
-                    "Unable to parse genesis block transaction to json_serde Value::String: {}",
-                    e
-                )
+                Log::new_panic(LogLevel::ERROR, 12, Some(vec![format!("{:?}", path)]));
+                panic!("Error parsing data file content at {:?}: {}", path, e);
             }
         };
-        let transactions = match to_value(transactions) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 1, Some(vec!["Value".to_string()]));
-                panic!(
-                    "Unable to parse genesis block transaction to json_serde Value: {}",
-                    e
-                )
+        if let Some(blockchain) = base_data["blockchain"].as_array() {
+            if !blockchain.is_empty() {
+                return;
             }
-        };
-        let hash = hash_block(
-            &String::from("0"),
-            &String::from("N/A"),
-            &transactions.to_string(),
+        }
+        FileOps::write(path, "blockchain", Block::genesis());
+    }
+
+    /// Computes the SHA256 hash of this Blocks header
+    /// (timestamp, previous hash, nonce, and merkle root)
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    pub fn calculate_hash(&self) -> String {
+        self.hash_with(HashAlgo::default())
+    }
+
+    /// Computes the hash of this Blocks header with the
+    /// given algorithm
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// algo: HashAlgo -> hash function to use
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    pub fn hash_with(&self, algo: HashAlgo) -> String {
+        hash_block_header_with(
+            algo,
+            &self.timestamp,
+            &self.previous_hash,
+            &self.nonce.to_string(),
+            &self.merkle_root,
+        )
+    }
+
+    /// Checks this Block has not been tampered with. The
+    /// stored hash must match a freshly computed hash and
+    /// meet the difficulty target, the previous hash must
+    /// link to `previous`, and each transaction hash and
+    /// the merkle root must match the transaction data
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// previous: &Block  -> the Block before this one in the chain
+    /// difficulty: usize -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    #[allow(dead_code)]
+    pub fn is_valid(&self, previous: &Block, difficulty: usize) -> bool {
+        self.is_valid_with(previous, difficulty, HashAlgo::default())
+    }
+
+    /// Checks this Block like is_valid, recomputing the
+    /// hash with the algorithm it was mined with
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// previous: &Block  -> the Block before this one in the chain
+    /// difficulty: usize -> number of leading zeros required
+    /// algo: HashAlgo    -> hash function of the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn is_valid_with(&self, previous: &Block, difficulty: usize, algo: HashAlgo) -> bool {
+        if self.hash != self.hash_with(algo) || self.previous_hash != previous.hash {
+            return false;
+        }
+        if !self.hash.starts_with(&"0".repeat(difficulty)) {
+            return false;
+        }
+        let transactions = self.transaction_list();
+        for t in &transactions {
+            if t["hash"] != Transaction::content_hash(t) {
+                return false;
+            }
+        }
+        self.merkle_root == merkle_root(&transactions)
+    }
+
+    /// Adds a coinbase transaction as the first transaction
+    /// in this Block paying `reward` plus the fees of every
+    /// other transaction to the miner, then recomputes the
+    /// merkle root
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// miner: &str -> wallet address of the miner
+    /// reward: u32 -> block reward before fees
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn add_coinbase(&mut self, miner: &str, reward: u32) {
+        let mut transactions = self.transaction_list();
+        let fees: i64 = transactions
+            .iter()
+            .map(|t| t["fee"].as_i64().unwrap_or(0))
+            .sum();
+        let amount = reward as i64 + fees;
+        transactions.insert(
+            0,
+            json!({
+                "hash": hash_transaction(COINBASE_ADDRESS, miner, &amount.to_string()),
+                "from_address": COINBASE_ADDRESS,
+                "to_address": miner,
+                "amount": amount,
+                "fee": 0,
+                "signature": "",
+            }),
         );
-        let merkle_root = get_merkle_root(TRANSACTIONS_PATH.as_path());
-        let genesis_block = Block {
-            timestamp,
-            hash,
-            previous_hash: String::from("N/A"),
+        self.merkle_root = merkle_root(&transactions);
+        self.transactions = json!({ "transactions": transactions });
+    }
+
+    /// Gets the list of transactions held in this Block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<Value>
+    /// ```
+    pub fn transaction_list(&self) -> Vec<Value> {
+        match self.transactions["transactions"].as_array() {
+            Some(transactions) => transactions.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Mines this Block by incrementing the nonce until
+    /// the block hash starts with `difficulty` leading
+    /// zeros, then stores the winning hash
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// difficulty: usize -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    #[allow(dead_code)]
+    pub fn mine(&mut self, difficulty: usize) {
+        self.mine_with(difficulty, HashAlgo::default());
+    }
+
+    /// Mines this Block like mine, hashing with the given
+    /// algorithm
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// difficulty: usize -> number of leading zeros required
+    /// algo: HashAlgo    -> hash function of the chain
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn mine_with(&mut self, difficulty: usize, algo: HashAlgo) {
+        let leading_zeros = "0".repeat(difficulty);
+        let mut hash = self.hash_with(algo);
+        while !hash.starts_with(&leading_zeros) {
+            self.nonce += 1;
+            hash = self.hash_with(algo);
+        }
+        self.hash = hash;
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_block {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use crate::mods::{helpers::parse_timestamp, wallet::Wallet};
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let now = parse_timestamp(&now_rfc3339()).unwrap();
+        let drift = SystemTime::now().duration_since(now).unwrap_or_default();
+
+        assert!(drift < Duration::from_secs(5));
+        assert!(parse_timestamp(GENESIS_TIMESTAMP).is_ok());
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_timestamps_increase() {
+        let first = Block::new(String::new(), vec![]);
+        thread::sleep(Duration::from_secs(1));
+        let second = Block::new(String::new(), vec![]);
+
+        let first = parse_timestamp(&first.timestamp).unwrap();
+        let second = parse_timestamp(&second.timestamp).unwrap();
+
+        assert!(second > first);
+        assert_eq!(
+            parse_timestamp(GENESIS_TIMESTAMP),
+            parse_timestamp(&Block::genesis().timestamp)
+        );
+    }
+
+    #[test]
+    fn test_from_value() {
+        let mut block = Block::new(Block::genesis().hash, vec![json!({ "amount": 5 })]);
+        block.mine(1);
+        let value = serde_json::to_value(&block).unwrap();
+        let mut tampered_root = value.clone();
+        tampered_root["merkle_root"] = Value::from("f".repeat(64));
+        let mut tampered_data = value.clone();
+        tampered_data["transactions"]["transactions"][0]["amount"] = Value::from(500);
+        let mut tampered_nonce = value.clone();
+        tampered_nonce["nonce"] = Value::from(block.nonce + 1);
+
+        assert_eq!(block.hash, Block::from_value(&value).unwrap().hash);
+        let genesis = serde_json::to_value(Block::genesis()).unwrap();
+        assert!(Block::from_value(&genesis).is_ok());
+        assert!(Block::from_value(&tampered_root)
+            .unwrap_err()
+            .contains("merkle root"));
+        assert!(Block::from_value(&tampered_data)
+            .unwrap_err()
+            .contains("merkle root"));
+        assert!(Block::from_value(&tampered_nonce)
+            .unwrap_err()
+            .contains("hash"));
+        assert!(Block::from_value(&json!({ "hash": "0" })).is_err());
+    }
+
+    #[test]
+    fn test_hash_algo() {
+        let algos = [HashAlgo::Sha256, HashAlgo::Sha3_256, HashAlgo::Blake3];
+        let genesis = Block::genesis();
+        for algo in algos {
+            let mut block = Block::new(genesis.hash.clone(), vec![]);
+            block.mine_with(2, algo);
+            let value = serde_json::to_value(&block).unwrap();
+
+            assert!(block.hash.starts_with("00"));
+            for other in algos {
+                assert_eq!(other == algo, block.is_valid_with(&genesis, 2, other));
+                assert_eq!(other == algo, Block::from_value_with(&value, other).is_ok());
+            }
+        }
+        assert_eq!(
+            Block::genesis().calculate_hash(),
+            Block::genesis().hash_with(HashAlgo::Sha256)
+        );
+    }
+
+    #[test]
+    fn test_genesis() {
+        let genesis = Block::genesis();
+
+        assert_eq!(genesis.hash, Block::genesis().hash);
+        assert_eq!(genesis.hash, genesis.calculate_hash());
+        assert_eq!("0".repeat(64), genesis.previous_hash);
+        assert!(genesis.transaction_list().is_empty());
+    }
+
+    #[test]
+    fn test_add_coinbase() {
+        let miner = "0".repeat(130);
+        let path = temp_dir().join("mockchain_test_add_coinbase.json");
+        let wallets = json!({ "wallets": [{ "name": "miner", "address": miner, "balance": 0 }] });
+        fs::write(&path, wallets.to_string()).unwrap();
+
+        let mut block = Block::new(
+            "0".repeat(64),
+            vec![
+                json!({ "from_address": "a", "to_address": "b", "amount": 10, "fee": 2 }),
+                json!({ "from_address": "b", "to_address": "a", "amount": 5, "fee": 3 }),
+            ],
+        );
+        block.add_coinbase(&miner, BLOCK_REWARD);
+        block.mine(2);
+        let coinbase = &block.transaction_list()[0];
+        let balance = Wallet::update_balance(
+            &path,
+            miner.clone(),
+            coinbase["amount"].as_i64().unwrap(),
+            "add",
+        );
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Ok(BLOCK_REWARD as i64 + 5), balance);
+        assert_eq!(3, block.transaction_list().len());
+        assert_eq!(block.merkle_root, merkle_root(&block.transaction_list()));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let genesis = Block::genesis();
+        let mut block = Block::new(genesis.hash.clone(), vec![]);
+        block.mine(2);
+
+        let mut unlinked = Block::new("f".repeat(64), vec![]);
+        unlinked.mine(2);
+        let mut tampered = block.clone();
+        tampered.nonce += 1;
+
+        assert!(block.is_valid(&genesis, 2));
+        assert!(!unlinked.is_valid(&genesis, 2));
+        assert!(!tampered.is_valid(&genesis, 2));
+        // one more leading zero than the mined hash happens to have
+        let zeros = block.hash.chars().take_while(|c| *c == '0').count();
+        assert!(!block.is_valid(&genesis, zeros + 1));
+    }
+
+    #[test]
+    fn test_mine() {
+        let mut block = Block {
+            timestamp: String::from("2024-06-01T00:00:00+00:00"),
+            hash: String::new(),
+            previous_hash: "0".repeat(64),
             nonce: 0,
-            transactions,
-            merkle_root,
+            transactions: Value::Array(vec![]),
+            merkle_root: String::from("None"),
         };
-        FileOps::write(BLOCKCHAIN_PATH.as_path(), "blockchain", genesis_block);
+
+        block.mine(2);
+
+        assert!(block.hash.starts_with("00"));
+        assert_eq!(block.hash, block.calculate_hash());
     }
 }
@@ -0,0 +1,1146 @@
+/*
+    Chain level operations over blockchain.json
+*/
+
+// std library
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+// 3rd party crates
+use serde::Serialize;
+use serde_json::{from_str, from_value, to_string, to_value, Value};
+
+// imports
+use super::{
+    base::Blockchain,
+    block::{Block, COINBASE_ADDRESS, DIFFICULTY, MAX_DIFFICULTY, MIN_DIFFICULTY},
+    crypto::{HashAlgo, KeyPair},
+    events::{Event, LedgerEvents},
+    file::FileOps,
+    helpers::parse_timestamp,
+    merkle::merkle_root,
+    transaction::Transaction,
+};
+
+/// Read-only overview of a chain
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// blocks: usize              -> number of blocks including genesis
+/// transactions: usize        -> number of transactions in every block
+/// tip_hash: Option<String>   -> hash of the last block
+/// difficulty: Option<usize>  -> difficulty recorded in blockchain.json
+/// span_secs: u64             -> seconds between genesis and the tip
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, Debug, Default, PartialEq
+/// ```
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ChainSummary {
+    pub blocks: usize,
+    pub transactions: usize,
+    pub tip_hash: Option<String>,
+    pub difficulty: Option<usize>,
+    pub span_secs: u64,
+}
+
+/// A problem found in one block by Blockchain::audit
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// index: usize        -> position of the block in the chain
+/// description: String -> what is wrong with the block
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, Debug, PartialEq
+/// ```
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChainIssue {
+    pub index: usize,
+    pub description: String,
+}
+
+impl Blockchain {
+    /// Walks every block in blockchain.json and checks
+    /// each one against the block before it. The genesis
+    /// block only needs a consistent hash
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path       -> path to blockchain.json
+    /// difficulty: usize -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), usize> -> index of the first invalid block (0 if
+    ///                      the file itself cannot be read)
+    /// ```
+    pub fn validate_chain(path: &Path, difficulty: usize) -> Result<(), usize> {
+        let base_data = FileOps::parse(path).map_err(|_| 0usize)?;
+        let algo = Blockchain::recorded_algo(&base_data).map_err(|_| 0usize)?;
+        let blockchain = match base_data["blockchain"].as_array() {
+            Some(data) => data,
+            None => return Err(0),
+        };
+
+        let mut blocks = Vec::new();
+        for (i, value) in blockchain.iter().enumerate() {
+            blocks.push(from_value(value.clone()).map_err(|_| i)?);
+        }
+        Blockchain::validate_blocks(&blocks, difficulty, algo)
+    }
+
+    /// Checks every block in blockchain.json and reports
+    /// every problem found rather than stopping at the
+    /// first. Blocks are checked for broken hash links,
+    /// header hashes, difficulty and merkle roots, their
+    /// transactions for content hashes and signatures, and
+    /// balances are replayed from genesis to catch any
+    /// account that is overdrawn
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path       -> path to blockchain.json
+    /// difficulty: usize -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<ChainIssue> -> empty if the chain is healthy
+    /// ```
+    pub fn audit(path: &Path, difficulty: usize) -> Vec<ChainIssue> {
+        let mut issues = Vec::new();
+        let mut report =
+            |index: usize, description: String| issues.push(ChainIssue { index, description });
+        let base_data = match FileOps::parse_validated(path, "blockchain") {
+            Ok(data) => data,
+            Err(e) => {
+                report(0, e);
+                return issues;
+            }
+        };
+        let algo = match Blockchain::recorded_algo(&base_data) {
+            Ok(algo) => algo,
+            Err(e) => {
+                report(0, e);
+                HashAlgo::default()
+            }
+        };
+
+        let mut balances: HashMap<String, i64> = HashMap::new();
+        let mut previous: Option<Block> = None;
+        for (i, value) in base_data["blockchain"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let block: Block = match from_value(value.clone()) {
+                Ok(block) => block,
+                Err(e) => {
+                    report(i, format!("Malformed block: {}", e));
+                    continue;
+                }
+            };
+
+            let prev = match &previous {
+                Some(prev) => prev,
+                None => {
+                    if block.hash != block.calculate_hash() {
+                        report(
+                            i,
+                            "Genesis block hash does not match its header".to_string(),
+                        );
+                    }
+                    previous = Some(block);
+                    continue;
+                }
+            };
+            if block.previous_hash != prev.hash {
+                report(
+                    i,
+                    format!("Previous hash does not match the hash of block {}", i - 1),
+                );
+            }
+            if block.hash != block.hash_with(algo) {
+                report(i, "Block hash does not match its header".to_string());
+            }
+            if !block.hash.starts_with(&"0".repeat(difficulty)) {
+                report(
+                    i,
+                    format!("Block hash does not meet a difficulty of {}", difficulty),
+                );
+            }
+            let transactions = block.transaction_list();
+            if block.merkle_root != merkle_root(&transactions) {
+                report(
+                    i,
+                    "Merkle root does not match the block transactions".to_string(),
+                );
+            }
+
+            let mut senders = Vec::new();
+            for t in &transactions {
+                let txid = Transaction::txid(t);
+                if t["hash"] != Transaction::content_hash(t) {
+                    report(i, format!("Transaction {} does not match its hash", txid));
+                }
+                let from = t["from_address"].as_str().unwrap_or_default();
+                if from != COINBASE_ADDRESS {
+                    let signature = t["signature"].as_str().unwrap_or_default();
+                    let message = Transaction::canonical_bytes(t);
+                    if !KeyPair::verify_with_public_key(signature, from, &message) {
+                        report(i, format!("Transaction {} has an invalid signature", txid));
+                    }
+                    *balances.entry(from.to_string()).or_default() -= Transaction::cost(t);
+                    if !senders.contains(&from) {
+                        senders.push(from);
+                    }
+                }
+                for output in Transaction::outputs(t).unwrap_or_default() {
+                    *balances.entry(output.to).or_default() += output.amount;
+                }
+            }
+            for sender in senders {
+                let balance = balances.get(sender).copied().unwrap_or_default();
+                if balance < 0 {
+                    report(
+                        i,
+                        format!("Balance of {} is negative ({})", sender, balance),
+                    );
+                }
+            }
+            previous = Some(block);
+        }
+        issues
+    }
+
+    /// Gets the hash algorithm recorded for a chain under
+    /// the "hash_algo" key of blockchain.json, SHA-256 if
+    /// none has been recorded
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to blockchain.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<HashAlgo, String>
+    /// ```
+    pub fn hash_algo(path: &Path) -> Result<HashAlgo, String> {
+        let base_data = FileOps::parse_validated(path, "blockchain")?;
+        Blockchain::recorded_algo(&base_data)
+    }
+
+    /// Records the hash algorithm used to mine and
+    /// validate the blocks of a chain. It can only be
+    /// changed while the chain holds just the genesis
+    /// block, which always uses SHA-256
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path    -> path to blockchain.json
+    /// algo: HashAlgo -> hash function to use
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn set_hash_algo(path: &Path, algo: HashAlgo) -> Result<(), String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "blockchain")?;
+            if base_data["blockchain"].as_array().map_or(0, Vec::len) > 1 {
+                return Err(
+                    "The hash algorithm can only be changed before any blocks are mined"
+                        .to_string(),
+                );
+            }
+            base_data["hash_algo"] = to_value(algo).map_err(|e| e.to_string())?;
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Reads the "hash_algo" key of parsed blockchain.json
+    /// data
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// base_data: &Value -> parsed blockchain.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<HashAlgo, String>
+    /// ```
+    fn recorded_algo(base_data: &Value) -> Result<HashAlgo, String> {
+        match base_data.get("hash_algo") {
+            Some(algo) => from_value(algo.clone())
+                .map_err(|e| format!("Unknown hash algorithm {}: {}", algo, e)),
+            None => Ok(HashAlgo::default()),
+        }
+    }
+
+    /// Checks each block in a sequence against the block
+    /// before it. The first block only needs a consistent
+    /// hash
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// blocks: &[Block]  -> blocks in chain order
+    /// difficulty: usize -> number of leading zeros required
+    /// algo: HashAlgo    -> hash function the blocks were mined with
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), usize> -> index of the first invalid block
+    /// ```
+    fn validate_blocks(blocks: &[Block], difficulty: usize, algo: HashAlgo) -> Result<(), usize> {
+        let mut previous: Option<&Block> = None;
+        for (i, block) in blocks.iter().enumerate() {
+            let valid = match previous {
+                Some(prev) => block.is_valid_with(prev, difficulty, algo),
+                None => block.hash == block.calculate_hash(),
+            };
+            if !valid {
+                log::warn!("Block {} ({}) failed validation", i, block.hash);
+                return Err(i);
+            }
+            previous = Some(block);
+        }
+        Ok(())
+    }
+
+    /// Replaces the local chain with a candidate chain,
+    /// e.g. one received from a peer, if the candidate is
+    /// valid, starts from the same genesis block and is
+    /// strictly longer. This is the longest chain rule
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path           -> path to blockchain.json
+    /// incoming: Vec<Block>  -> candidate chain
+    /// difficulty: usize     -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, String> -> true if the local chain was replaced,
+    ///                         false if the candidate is not longer
+    /// ```
+    pub fn replace_chain(
+        path: &Path,
+        incoming: Vec<Block>,
+        difficulty: usize,
+    ) -> Result<bool, String> {
+        match incoming.first() {
+            Some(genesis) if genesis.hash == Block::genesis().hash => {}
+            _ => return Err("Candidate chain does not start from the genesis block".to_string()),
+        }
+        let algo = Blockchain::hash_algo(path)?;
+        if let Err(i) = Blockchain::validate_blocks(&incoming, difficulty, algo) {
+            return Err(format!("Candidate chain is invalid at block {}", i));
+        }
+
+        FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "blockchain")?;
+            let current = base_data["blockchain"].as_array().map_or(0, Vec::len);
+            if incoming.len() <= current {
+                return Ok(false);
+            }
+            // other keys such as hash_algo describe the chain and are kept
+            base_data["blockchain"] = to_value(&incoming).map_err(|e| e.to_string())?;
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            Ok(true)
+        })
+    }
+
+    /// Links a block to the current tip of the chain,
+    /// mines it and appends it to blockchain.json. The
+    /// block is rejected if it does not validate against
+    /// the tip after mining. The difficulty it was mined at
+    /// is recorded alongside hash_algo
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path       -> path to blockchain.json
+    /// block: Block      -> block to append
+    /// difficulty: usize -> number of leading zeros required
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn add_block(path: &Path, mut block: Block, difficulty: usize) -> Result<(), String> {
+        let index = FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "blockchain")?;
+            let algo = Blockchain::recorded_algo(&base_data)?;
+            let blockchain = match base_data["blockchain"].as_array_mut() {
+                Some(data) => data,
+                None => return Err("Failed to read blockchain.json".to_string()),
+            };
+            let tip: Block = match blockchain.last() {
+                Some(value) => from_value(value.clone()).map_err(|e| e.to_string())?,
+                None => return Err("Blockchain has no genesis block".to_string()),
+            };
+
+            block.previous_hash = tip.hash.clone();
+            block.mine_with(difficulty, algo);
+            if !block.is_valid_with(&tip, difficulty, algo) {
+                log::error!("Mined block {} failed validation", block.hash);
+                return Err(format!("Mined block {} failed validation", block.hash));
+            }
+
+            let height = blockchain.len();
+            blockchain.push(to_value(&block).map_err(|e| e.to_string())?);
+            base_data["difficulty"] = to_value(difficulty).map_err(|e| e.to_string())?;
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            log::info!(
+                "Block {} mined at height {} with nonce {}",
+                block.hash,
+                height,
+                block.nonce
+            );
+            Ok(height)
+        })?;
+        LedgerEvents::emit(Event::BlockAdded {
+            path: path.to_path_buf(),
+            index,
+            hash: block.hash,
+        });
+        Ok(())
+    }
+
+    /// Appends a block as one line of JSON to a
+    /// newline-delimited chain file such as
+    /// blockchain.ndjson. Only the new line is written so
+    /// the cost does not grow with the length of the chain.
+    /// The block is not linked or validated
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path   -> path to blockchain.ndjson
+    /// block: &Block -> block to append
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn append_block_fast(path: &Path, block: &Block) -> Result<(), String> {
+        let mut line = to_string(block).map_err(|e| e.to_string())?;
+        line.push('\n');
+        FileOps::with_lock(path, || {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| e.to_string())?;
+            file.write_all(line.as_bytes()).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Streams the blocks of a newline-delimited chain
+    /// file one line at a time, in chain order
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to blockchain.ndjson
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<impl Iterator<Item = Result<Block, String>>, String>
+    /// ```
+    pub fn read_blocks_fast(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = Result<Block, String>>, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        Ok(BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| {
+                let line = line.map_err(|e| e.to_string())?;
+                from_str(&line).map_err(|e| e.to_string())
+            }))
+    }
+
+    /// Copies every block from the array format of
+    /// blockchain.json into a newline-delimited chain file,
+    /// replacing anything already at `to`
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// from: &Path -> path to blockchain.json
+    /// to: &Path   -> path to blockchain.ndjson
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<usize, String> -> number of blocks migrated
+    /// ```
+    pub fn migrate_to_ndjson(from: &Path, to: &Path) -> Result<usize, String> {
+        let base_data = FileOps::parse_validated(from, "blockchain")?;
+        let blockchain = base_data["blockchain"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+        let mut contents = String::new();
+        for block in blockchain {
+            contents.push_str(&block.to_string());
+            contents.push('\n');
+        }
+        fs::write(to, contents).map_err(|e| e.to_string())?;
+        Ok(blockchain.len())
+    }
+
+    /// Reads the block at `index` from blockchain.json,
+    /// the genesis block being index 0. A block whose hash
+    /// or merkle root does not match its contents is not
+    /// returned
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path  -> path to blockchain.json
+    /// index: usize -> position of the block in the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<Block>
+    /// ```
+    pub fn get_block_by_index(path: &Path, index: usize) -> Option<Block> {
+        let base_data = FileOps::parse(path).ok()?;
+        let algo = Blockchain::recorded_algo(&base_data).ok()?;
+        let value = base_data["blockchain"].as_array()?.get(index)?;
+        Block::from_chain_value(value, index, algo).ok()
+    }
+
+    /// Reads the block with the given hash from
+    /// blockchain.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to blockchain.json
+    /// hash: &str  -> hash of the block to find
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<Block>
+    /// ```
+    pub fn get_block_by_hash(path: &Path, hash: &str) -> Option<Block> {
+        let base_data = FileOps::parse(path).ok()?;
+        let algo = Blockchain::recorded_algo(&base_data).ok()?;
+        let blockchain = base_data["blockchain"].as_array()?;
+        let index = blockchain.iter().position(|block| block["hash"] == hash)?;
+        Block::from_chain_value(&blockchain[index], index, algo).ok()
+    }
+
+    /// Summarises blockchain.json without validating it.
+    /// An empty chain has no tip and a chain of one block
+    /// spans 0 seconds. A file that cannot be read gives a
+    /// zeroed summary and a span of 0 is given if the first
+    /// or last timestamp cannot be read
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to blockchain.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// ChainSummary
+    /// ```
+    pub fn summary(path: &Path) -> ChainSummary {
+        let base_data = match FileOps::parse_validated(path, "blockchain") {
+            Ok(data) => data,
+            Err(_) => return ChainSummary::default(),
+        };
+        let blockchain = base_data["blockchain"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+
+        let transactions = blockchain
+            .iter()
+            .filter_map(|block| block["transactions"]["transactions"].as_array())
+            .map(Vec::len)
+            .sum();
+        let timestamp = |block: Option<&Value>| {
+            block
+                .and_then(|b| b["timestamp"].as_str())
+                .and_then(|t| parse_timestamp(t).ok())
+        };
+        let span_secs = match (timestamp(blockchain.first()), timestamp(blockchain.last())) {
+            (Some(first), Some(last)) => last.duration_since(first).unwrap_or_default().as_secs(),
+            _ => 0,
+        };
+
+        ChainSummary {
+            blocks: blockchain.len(),
+            transactions,
+            tip_hash: blockchain
+                .last()
+                .and_then(|tip| tip["hash"].as_str())
+                .map(String::from),
+            difficulty: base_data["difficulty"].as_u64().map(|d| d as usize),
+            span_secs,
+        }
+    }
+
+    /// Works out the mining difficulty from how fast the
+    /// last `window` blocks were mined. Block timestamps
+    /// are RFC3339 strings. If blocks arrive faster than
+    /// the target the difficulty recorded in blockchain.json
+    /// goes up by one, if they arrive slower it goes down by
+    /// one, staying within MIN_DIFFICULTY and MAX_DIFFICULTY.
+    /// The genesis block is ignored as its timestamp is
+    /// fixed
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path                 -> path to blockchain.json
+    /// target_block_time_secs: u64 -> desired seconds between blocks
+    /// window: usize               -> number of recent blocks to look at
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize -> the recorded difficulty (or DIFFICULTY) if there are too few blocks to measure
+    /// ```
+    pub fn adjust_difficulty(path: &Path, target_block_time_secs: u64, window: usize) -> usize {
+        let base_data = match FileOps::parse(path) {
+            Ok(data) => data,
+            Err(_) => return DIFFICULTY,
+        };
+        let current = base_data["difficulty"]
+            .as_u64()
+            .map_or(DIFFICULTY, |d| d as usize)
+            .clamp(MIN_DIFFICULTY, MAX_DIFFICULTY);
+        let blockchain = match base_data["blockchain"].as_array() {
+            Some(data) if data.len() > 1 => &data[1..],
+            _ => return current,
+        };
+
+        let recent = &blockchain[blockchain.len().saturating_sub(window)..];
+        let mut timestamps = Vec::new();
+        for block in recent {
+            match block["timestamp"].as_str().map(parse_timestamp) {
+                Some(Ok(timestamp)) => timestamps.push(timestamp),
+                _ => return current,
+            }
+        }
+        if timestamps.len() < 2 {
+            return current;
+        }
+
+        let elapsed = timestamps[timestamps.len() - 1]
+            .duration_since(timestamps[0])
+            .unwrap_or_default();
+        let average = elapsed.as_secs() as i64 / (timestamps.len() as i64 - 1);
+        let target = target_block_time_secs as i64;
+        if average < target {
+            (current + 1).min(MAX_DIFFICULTY)
+        } else if average > target {
+            current.saturating_sub(1).max(MIN_DIFFICULTY)
+        } else {
+            current
+        }
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_blockchain {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::path::PathBuf;
+
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use crate::mods::{block::GENESIS_TIMESTAMP, crypto::hash_transaction};
+
+    fn transaction(from: &str, to: &str, amount: i64) -> Value {
+        json!({
+            "hash": hash_transaction(from, to, &amount.to_string()),
+            "from_address": from,
+            "to_address": to,
+            "amount": amount,
+            "signature": "0".repeat(128),
+        })
+    }
+
+    fn build_chain(transactions: Vec<Vec<Value>>) -> Vec<Block> {
+        let mut chain = vec![Block::genesis()];
+        for (i, t) in transactions.into_iter().enumerate() {
+            let mut block = Block {
+                timestamp: format!("2024-06-01T00:0{}:00+00:00", i + 1),
+                hash: String::new(),
+                previous_hash: chain[chain.len() - 1].hash.clone(),
+                nonce: 0,
+                merkle_root: merkle_root(&t),
+                transactions: json!({ "transactions": t }),
+            };
+            block.mine(2);
+            chain.push(block);
+        }
+        chain
+    }
+
+    fn timed_chain(interval_secs: i64, count: usize) -> Vec<Block> {
+        let start = DateTime::parse_from_rfc3339("2024-06-01T00:00:00+00:00").unwrap();
+        let mut chain = vec![Block::genesis()];
+        for i in 0..count {
+            let timestamp = start + chrono::Duration::seconds(interval_secs * i as i64);
+            let mut block = Block::new(chain[chain.len() - 1].hash.clone(), vec![]);
+            block.timestamp = timestamp.to_rfc3339();
+            block.hash = block.calculate_hash();
+            chain.push(block);
+        }
+        chain
+    }
+
+    fn write_chain(file_name: &str, chain: &[Block]) -> PathBuf {
+        let path = temp_dir().join(file_name);
+        fs::write(&path, json!({ "blockchain": chain }).to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_append_block_fast() {
+        let path = temp_dir().join("mockchain_test_append_fast.ndjson");
+        let _ = fs::remove_file(&path);
+        let mut hashes = Vec::new();
+        let mut previous = Block::genesis();
+        for i in 0..1000 {
+            let mut block = Block::new(previous.hash.clone(), vec![transaction("a", "b", i)]);
+            block.hash = block.calculate_hash();
+            Blockchain::append_block_fast(&path, &block).unwrap();
+            hashes.push(block.hash.clone());
+            previous = block;
+        }
+
+        let blocks: Result<Vec<Block>, String> =
+            Blockchain::read_blocks_fast(&path).unwrap().collect();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("ndjson.lock"));
+
+        let read: Vec<String> = blocks.unwrap().into_iter().map(|b| b.hash).collect();
+        assert_eq!(hashes, read);
+    }
+
+    #[test]
+    fn test_migrate_to_ndjson() {
+        let chain = build_chain(vec![vec![transaction("alice", "bob", 1)]]);
+        let from = write_chain("mockchain_test_migrate.json", &chain);
+        let to = temp_dir().join("mockchain_test_migrate.ndjson");
+
+        let migrated = Blockchain::migrate_to_ndjson(&from, &to);
+        let blocks: Vec<Block> = Blockchain::read_blocks_fast(&to)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        fs::remove_file(&from).unwrap();
+        fs::remove_file(&to).unwrap();
+
+        assert_eq!(Ok(2), migrated);
+        assert_eq!(chain[1].hash, blocks[1].hash);
+    }
+
+    #[test]
+    fn test_replace_chain_longer() {
+        let long = build_chain(vec![
+            vec![transaction("alice", "bob", 1)],
+            vec![transaction("bob", "carol", 2)],
+        ]);
+        let path = write_chain("mockchain_test_replace_longer.json", &long[..2]);
+
+        let replaced = Blockchain::replace_chain(&path, long.clone(), 2);
+        let tip = Blockchain::get_block_by_index(&path, 2);
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Ok(true), replaced);
+        assert_eq!(long[2].hash, tip.unwrap().hash);
+    }
+
+    #[test]
+    fn test_replace_chain_equal_length() {
+        let local = build_chain(vec![vec![transaction("alice", "bob", 1)]]);
+        let other = build_chain(vec![vec![transaction("alice", "carol", 3)]]);
+        let path = write_chain("mockchain_test_replace_equal.json", &local);
+
+        let replaced = Blockchain::replace_chain(&path, other, 2);
+        let tip = Blockchain::get_block_by_index(&path, 1);
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Ok(false), replaced);
+        assert_eq!(local[1].hash, tip.unwrap().hash);
+    }
+
+    #[test]
+    fn test_replace_chain_invalid() {
+        let mut long = build_chain(vec![
+            vec![transaction("alice", "bob", 1)],
+            vec![transaction("bob", "carol", 2)],
+        ]);
+        long[1].nonce += 1;
+        let path = write_chain("mockchain_test_replace_invalid.json", &long[..1]);
+
+        let replaced = Blockchain::replace_chain(&path, long, 2);
+        let data = FileOps::parse(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Err("Candidate chain is invalid at block 1".to_string()),
+            replaced
+        );
+        assert_eq!(1, data["blockchain"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_add_block() {
+        let path = write_chain("mockchain_test_add_block.json", &[Block::genesis()]);
+        for i in 0..3 {
+            let t = transaction("alice", "bob", i + 1);
+            let block = Block::new(String::from("not the tip"), vec![t]);
+            Blockchain::add_block(&path, block, DIFFICULTY).unwrap();
+        }
+
+        let data = FileOps::parse(&path).unwrap();
+        let valid = Blockchain::validate_chain(&path, DIFFICULTY);
+        let summary = Blockchain::summary(&path);
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        let blockchain = data["blockchain"].as_array().unwrap();
+        assert_eq!(4, blockchain.len());
+        assert_eq!(DIFFICULTY, data["difficulty"]);
+        assert_eq!(Some(DIFFICULTY), summary.difficulty);
+        for i in 1..blockchain.len() {
+            assert_eq!(blockchain[i - 1]["hash"], blockchain[i]["previous_hash"]);
+        }
+        assert_eq!(Ok(()), valid);
+    }
+
+    #[test]
+    fn test_validate_chain() {
+        let chain = build_chain(vec![
+            vec![transaction("REWARD", "alice", 50)],
+            vec![transaction("alice", "bob", 20)],
+            vec![transaction("bob", "alice", 5)],
+        ]);
+        let path = write_chain("mockchain_test_validate_chain.json", &chain);
+
+        let result = Blockchain::validate_chain(&path, 2);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Ok(()), result);
+    }
+
+    #[test]
+    fn test_validate_chain_tampered() {
+        let mut chain = build_chain(vec![
+            vec![transaction("REWARD", "alice", 50)],
+            vec![transaction("alice", "bob", 20)],
+            vec![transaction("bob", "alice", 5)],
+        ]);
+        chain[2].transactions["transactions"][0]["amount"] = json!(2000);
+        let path = write_chain("mockchain_test_validate_chain_tampered.json", &chain);
+
+        let result = Blockchain::validate_chain(&path, 2);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Err(2), result);
+    }
+
+    #[test]
+    fn test_summary() {
+        let chain = build_chain(vec![
+            vec![transaction("REWARD", "alice", 50)],
+            vec![
+                transaction("alice", "bob", 20),
+                transaction("bob", "alice", 5),
+            ],
+        ]);
+        let path = temp_dir().join("mockchain_test_summary.json");
+        fs::write(
+            &path,
+            json!({ "blockchain": chain, "difficulty": 2 }).to_string(),
+        )
+        .unwrap();
+        let summary = Blockchain::summary(&path);
+        let genesis_only = Blockchain::summary(&write_chain(
+            "mockchain_test_summary.json",
+            &[Block::genesis()],
+        ));
+        let empty = Blockchain::summary(&write_chain("mockchain_test_summary.json", &[]));
+        let mut unreadable_time = chain.clone();
+        unreadable_time[2].timestamp = String::from("not a time");
+        let untimed = Blockchain::summary(&write_chain(
+            "mockchain_test_summary.json",
+            &unreadable_time,
+        ));
+        fs::remove_file(&path).unwrap();
+        let missing = Blockchain::summary(&path);
+
+        let start = parse_timestamp(GENESIS_TIMESTAMP).unwrap();
+        let tip = parse_timestamp(&chain[2].timestamp).unwrap();
+        assert_eq!(
+            ChainSummary {
+                blocks: 3,
+                transactions: 3,
+                tip_hash: Some(chain[2].hash.clone()),
+                difficulty: Some(2),
+                span_secs: tip.duration_since(start).unwrap().as_secs(),
+            },
+            summary
+        );
+        assert_eq!(
+            ChainSummary {
+                blocks: 1,
+                transactions: 0,
+                tip_hash: Some(Block::genesis().hash),
+                difficulty: None,
+                span_secs: 0,
+            },
+            genesis_only
+        );
+        assert_eq!(
+            ChainSummary {
+                blocks: 0,
+                transactions: 0,
+                tip_hash: None,
+                difficulty: None,
+                span_secs: 0,
+            },
+            empty
+        );
+        assert_eq!(0, untimed.span_secs);
+        assert_eq!(3, untimed.blocks);
+        assert_eq!(ChainSummary::default(), missing);
+    }
+
+    #[test]
+    fn test_adjust_difficulty_faster() {
+        let path = write_chain(
+            "mockchain_test_adjust_difficulty_faster.json",
+            &timed_chain(10, 6),
+        );
+
+        let difficulty = Blockchain::adjust_difficulty(&path, 60, 5);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(DIFFICULTY + 1, difficulty);
+    }
+
+    #[test]
+    fn test_adjust_difficulty_slower() {
+        let path = write_chain(
+            "mockchain_test_adjust_difficulty_slower.json",
+            &timed_chain(300, 6),
+        );
+
+        let difficulty = Blockchain::adjust_difficulty(&path, 60, 5);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(DIFFICULTY - 1, difficulty);
+    }
+
+    #[test]
+    fn test_adjust_difficulty_recorded() {
+        let path = temp_dir().join("mockchain_test_adjust_difficulty_recorded.json");
+        let adjusted = |interval_secs: i64, difficulty: usize| {
+            let data =
+                json!({ "blockchain": timed_chain(interval_secs, 6), "difficulty": difficulty });
+            fs::write(&path, data.to_string()).unwrap();
+            Blockchain::adjust_difficulty(&path, 60, 5)
+        };
+
+        let raised = adjusted(10, 3);
+        let capped = adjusted(10, MAX_DIFFICULTY);
+        let lowered = adjusted(300, 3);
+        let floored = adjusted(300, MIN_DIFFICULTY);
+        let steady = adjusted(60, 3);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(4, raised);
+        assert_eq!(MAX_DIFFICULTY, capped);
+        assert_eq!(2, lowered);
+        assert_eq!(MIN_DIFFICULTY, floored);
+        assert_eq!(3, steady);
+    }
+
+    #[test]
+    fn test_get_block() {
+        let chain = build_chain(vec![
+            vec![transaction("REWARD", "alice", 50)],
+            vec![transaction("alice", "bob", 20)],
+            vec![transaction("bob", "alice", 5)],
+        ]);
+        let path = write_chain("mockchain_test_get_block.json", &chain);
+
+        let by_index = Blockchain::get_block_by_index(&path, 2);
+        let by_hash = Blockchain::get_block_by_hash(&path, &chain[2].hash);
+        let out_of_range = Blockchain::get_block_by_index(&path, 4);
+        let unknown = Blockchain::get_block_by_hash(&path, &"f".repeat(64));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(chain[2].hash, by_index.unwrap().hash);
+        assert_eq!(chain[2].transactions, by_hash.unwrap().transactions);
+        assert!(out_of_range.is_none());
+        assert!(unknown.is_none());
+    }
+
+    #[test]
+    fn test_recorded_hash_algo() {
+        let path = write_chain("mockchain_test_hash_algo.json", &[Block::genesis()]);
+        let default = Blockchain::hash_algo(&path);
+        Blockchain::set_hash_algo(&path, HashAlgo::Blake3).unwrap();
+        let t = transaction("alice", "bob", 5);
+        Blockchain::add_block(&path, Block::new(String::new(), vec![t]), DIFFICULTY).unwrap();
+
+        let recorded = Blockchain::hash_algo(&path);
+        let valid = Blockchain::validate_chain(&path, DIFFICULTY);
+        let genesis = Blockchain::get_block_by_index(&path, 0);
+        let mined = Blockchain::get_block_by_index(&path, 1);
+        let by_hash = mined
+            .as_ref()
+            .and_then(|block| Blockchain::get_block_by_hash(&path, &block.hash));
+        let locked = Blockchain::set_hash_algo(&path, HashAlgo::Sha256);
+        let mut data = FileOps::parse(&path).unwrap();
+        data["hash_algo"] = json!("sha256");
+        fs::write(&path, data.to_string()).unwrap();
+        let mismatched = Blockchain::validate_chain(&path, DIFFICULTY);
+        let hashed_with_other = by_hash
+            .as_ref()
+            .and_then(|block| Blockchain::get_block_by_hash(&path, &block.hash));
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Ok(HashAlgo::Sha256), default);
+        assert_eq!(Ok(HashAlgo::Blake3), recorded);
+        assert_eq!(Ok(()), valid);
+        // the genesis block keeps its SHA-256 hash
+        assert_eq!(Some(Block::genesis()), genesis);
+        let mined = mined.unwrap();
+        assert_eq!(mined.hash_with(HashAlgo::Blake3), mined.hash);
+        assert_eq!(Some(mined), by_hash);
+        assert!(locked.is_err());
+        assert_eq!(Err(1), mismatched);
+        assert!(hashed_with_other.is_none());
+    }
+
+    fn signed(signer: &KeyPair, from: &str, to: &str, amount: i64) -> Value {
+        let mut tx = transaction(from, to, amount);
+        tx["fee"] = json!(0);
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            signer.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+        tx
+    }
+
+    #[test]
+    fn test_audit() {
+        let alice = KeyPair::generate(String::from("alice"));
+        let mallory = KeyPair::generate(String::from("mallory"));
+        let a = alice.public_key.as_str();
+        let mut coinbase = transaction(COINBASE_ADDRESS, a, 50);
+        coinbase["fee"] = json!(0);
+        let chain = build_chain(vec![
+            vec![coinbase],
+            vec![signed(&alice, a, "bob", 20)],
+            vec![signed(&mallory, a, "bob", 5)],
+            vec![signed(&alice, a, "bob", 40)],
+        ]);
+        let healthy = write_chain("mockchain_test_audit.json", &chain[..3]);
+        let before = Blockchain::audit(&healthy, 2);
+        let path = write_chain("mockchain_test_audit.json", &chain);
+        let issues = Blockchain::audit(&path, 2);
+        fs::remove_file(&path).unwrap();
+        let unreadable = Blockchain::audit(&path, 2);
+
+        let forged = Transaction::txid(&chain[3].transaction_list()[0]);
+        assert!(before.is_empty());
+        assert_eq!(
+            vec![
+                ChainIssue {
+                    index: 3,
+                    description: format!("Transaction {} has an invalid signature", forged),
+                },
+                ChainIssue {
+                    index: 4,
+                    description: format!("Balance of {} is negative (-15)", a),
+                },
+            ],
+            issues
+        );
+        assert_eq!(1, unreadable.len());
+        assert_eq!(0, unreadable[0].index);
+    }
+}
@@ -0,0 +1,1542 @@
+/*
+    Command line subcommands wired to the library calls
+*/
+
+// std library
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+
+// 3rd party crates
+use clap::{Parser, Subcommand};
+use hex::decode;
+use p256::ecdsa::Signature;
+use serde_json::{from_value, to_string_pretty, Value};
+
+// imports
+use super::{
+    amount::Amount,
+    base::Blockchain,
+    block::{Block, MIN_DIFFICULTY},
+    config::Config,
+    crypto::{derive_address, validate_address, HashAlgo, KeyPair},
+    events::LedgerEvents,
+    file::FileOps,
+    helpers::{create_transaction, create_wallet, mine_block},
+    mempool,
+    merkle::{self, hash_leaf},
+    mnemonic::{self, to_mnemonic},
+    p2p::Node,
+    transaction::Transaction,
+    wallet::Wallet,
+};
+
+/// Command line arguments. With no subcommand the
+/// interactive menu is started
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// data_dir: Option<PathBuf> -> data directory overriding the default
+/// command: Option<Command>  -> subcommand to run
+/// ```
+///
+/// # Derives
+/// ```
+/// clap::Parser, Debug
+/// ```
+#[derive(Parser, Debug)]
+#[command(name = "mockchain_v2", about = "A mock blockchain to play with")]
+pub struct Cli {
+    /// directory holding the data files, defaults to
+    /// $MOCKCHAIN_DATA_DIR or ~/.mockchain/data
+    #[arg(long, global = true)]
+    pub data_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Top level subcommands
+///
+/// # Visibility
+/// public
+///
+/// # Derives
+/// ```
+/// clap::Subcommand, Debug
+/// ```
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create wallets and check balances
+    Wallet {
+        #[command(subcommand)]
+        action: WalletCommand,
+    },
+    /// Sign messages and manage key pairs
+    Key {
+        #[command(subcommand)]
+        action: KeyCommand,
+    },
+    /// Add a signed transaction to the mempool
+    Send {
+        from: String,
+        to: String,
+        amount: i64,
+        /// fee paid to the miner
+        #[arg(long, default_value_t = 0)]
+        fee: u32,
+        /// passphrase if the senders private key is encrypted
+        #[arg(long)]
+        passphrase: Option<String>,
+    },
+    /// Show how many transactions are waiting to be mined
+    Pending,
+    /// Mine the pending transactions into a new block
+    Mine { miner: String },
+    /// Check the integrity of the blockchain
+    Chain {
+        #[command(subcommand)]
+        action: ChainCommand,
+    },
+    /// Save or roll back every data file
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommand,
+    },
+    /// Run a p2p node that accepts blocks and chains from its peers
+    Node {
+        #[arg(default_value = "127.0.0.1:9000")]
+        addr: String,
+        /// node to send the local chain to, may be repeated
+        #[arg(long = "peer")]
+        peers: Vec<SocketAddr>,
+    },
+    /// Serve the REST API
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+}
+
+/// `wallet` subcommands
+///
+/// # Visibility
+/// public
+///
+/// # Derives
+/// ```
+/// clap::Subcommand, Debug
+/// ```
+#[derive(Subcommand, Debug)]
+pub enum WalletCommand {
+    /// Create new wallets and key pairs, all or none of them
+    Create {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Show the balances of wallets
+    Balance {
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// replay the chain up to this block height instead
+        #[arg(long)]
+        at: Option<usize>,
+    },
+    /// List every wallet with its balance
+    List,
+    /// Remove a wallet
+    Delete { name: String },
+    /// Rename a wallet. Only wallets.json is changed
+    Rename { old: String, new: String },
+    /// Move funds straight from one wallet to another without a block
+    Transfer {
+        from: String,
+        to: String,
+        amount: i64,
+        /// show the resulting balances without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add or subtract funds from a wallet without a transaction or block
+    Adjust {
+        name: String,
+        amount: i64,
+        /// add | subtract
+        op: String,
+        /// show the resulting balance without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the mined transactions sent from or paid to a wallet
+    History { name: String },
+    /// Show the sum of every wallet balance
+    Supply,
+    /// Find the wallet that owns an address
+    Find { address: String },
+    /// Write every wallet to a CSV file
+    Export { out: PathBuf },
+    /// Create a wallet from an existing hex encoded private key
+    Import { name: String, private_key: String },
+    /// Stop a wallet sending funds, it can still receive them
+    Freeze { name: String },
+    /// Let a frozen wallet send funds again
+    Unfreeze { name: String },
+}
+
+/// `key` subcommands
+///
+/// # Visibility
+/// public
+///
+/// # Derives
+/// ```
+/// clap::Subcommand, Debug
+/// ```
+#[derive(Subcommand, Debug)]
+pub enum KeyCommand {
+    /// Sign a message so the signer can be recovered from the signature
+    Sign {
+        name: String,
+        message: String,
+        /// passphrase if the private key is encrypted
+        #[arg(long)]
+        passphrase: Option<String>,
+    },
+    /// Find the wallet that produced a signature over a message
+    Recover { message: String, signature: String },
+    /// Encrypt a private key at rest with a passphrase
+    Encrypt { name: String, passphrase: String },
+    /// Print the recovery words for a private key
+    Backup {
+        name: String,
+        /// passphrase if the private key is encrypted
+        #[arg(long)]
+        passphrase: Option<String>,
+    },
+    /// Recreate a wallet from its recovery words
+    Restore {
+        name: String,
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
+    /// Print the short checksummed address of a wallet
+    Address { name: String },
+    /// Check the checksum of a short address
+    Check { address: String },
+}
+
+/// `chain` subcommands
+///
+/// # Visibility
+/// public
+///
+/// # Derives
+/// ```
+/// clap::Subcommand, Debug
+/// ```
+#[derive(Subcommand, Debug)]
+pub enum ChainCommand {
+    /// Validate every block in the chain
+    Validate,
+    /// Report every problem found in the chain
+    Audit,
+    /// Prove a transaction is included in a block
+    Proof { height: usize, position: usize },
+    /// Show a block by its height or hash
+    Block { id: String },
+    /// Write the chain as one block per line, appending only new blocks
+    Export { out: PathBuf },
+    /// Show the length, tip and difficulty of the chain
+    Summary,
+    /// Copy the chain to a file, gzip compressed if it ends in .gz
+    Archive { out: PathBuf },
+    /// Choose the block hash function before any blocks are mined
+    HashAlgo {
+        /// sha256 | sha3_256 | blake3
+        algo: String,
+    },
+}
+
+/// `snapshot` subcommands
+///
+/// # Visibility
+/// public
+///
+/// # Derives
+/// ```
+/// clap::Subcommand, Debug
+/// ```
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommand {
+    /// Copy the data files into a named snapshot
+    Create { name: String },
+    /// Copy a named snapshot back over the data files
+    Restore { name: String },
+}
+
+/// Runs a subcommand against the data files in
+/// `config` and returns the text to show the user
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// command: Command -> parsed subcommand
+/// config: &Config  -> locations of the data files
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, String>
+/// ```
+pub fn dispatch(command: Command, config: &Config) -> Result<String, String> {
+    match command {
+        Command::Wallet {
+            action: WalletCommand::Create { mut names },
+        } => {
+            let wallets = if names.len() == 1 {
+                vec![create_wallet(config, names.remove(0))?]
+            } else {
+                Wallet::create_many(&config.wallets_path, &config.keypairs_path, &names)?
+            };
+            let lines: Vec<String> = wallets
+                .iter()
+                .map(|wallet| {
+                    format!(
+                        "Wallet created for '{}' with address {}",
+                        wallet.name, wallet.address
+                    )
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        Command::Wallet {
+            action:
+                WalletCommand::Balance {
+                    names,
+                    at: Some(height),
+                },
+        } => {
+            if Blockchain::get_block_by_index(&config.blockchain_path, height).is_none() {
+                return Err(format!("No valid block found at height {}", height));
+            }
+            let mut lines = Vec::new();
+            for name in names {
+                let address = wallet_address(config, &name)?;
+                lines.push(format!(
+                    "'{}' had a balance of {} at block {}",
+                    name,
+                    Wallet::balance_at(&config.blockchain_path, &address, height),
+                    height
+                ));
+            }
+            Ok(lines.join("\n"))
+        }
+        Command::Wallet {
+            action: WalletCommand::Balance { names, at: None },
+        } => {
+            let balances = Wallet::get_balances(&FileOps {}, &config.wallets_path, &names)
+                .map_err(|e| e.to_string())?;
+            let mut lines = Vec::new();
+            for (name, balance) in balances {
+                match balance {
+                    Some(balance) => lines.push(format!("'{}' has a balance of {}", name, balance)),
+                    None => return Err(format!("No wallet found under name '{}'", name)),
+                }
+            }
+            Ok(lines.join("\n"))
+        }
+        Command::Wallet {
+            action: WalletCommand::List,
+        } => {
+            let wallets =
+                Wallet::list_all(&FileOps {}, &config.wallets_path).map_err(|e| e.to_string())?;
+            if wallets.is_empty() {
+                return Ok("No wallets found".to_string());
+            }
+            let lines: Vec<String> = wallets
+                .iter()
+                .map(|wallet| {
+                    let frozen = if wallet.frozen { " (frozen)" } else { "" };
+                    format!("{}: {}{}", wallet.name, wallet.balance, frozen)
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        Command::Wallet {
+            action: WalletCommand::Delete { name },
+        } => {
+            Wallet::delete(&FileOps {}, &config.wallets_path, &name)?;
+            Ok(format!("Wallet '{}' deleted", name))
+        }
+        Command::Wallet {
+            action: WalletCommand::Rename { old, new },
+        } => {
+            Wallet::rename(&FileOps {}, &config.wallets_path, &old, &new)?;
+            Ok(format!(
+                "Wallet '{}' renamed to '{}'; its key pair is still stored under '{}'",
+                old, new, old
+            ))
+        }
+        Command::Wallet {
+            action:
+                WalletCommand::Transfer {
+                    from,
+                    to,
+                    amount,
+                    dry_run: true,
+                },
+        } => {
+            let (sender, recipient) =
+                Wallet::preview_transfer(&config.wallets_path, &from, &to, amount)?;
+            Ok(format!(
+                "Transferring {} would leave '{}' with {} and '{}' with {}",
+                amount, from, sender, to, recipient
+            ))
+        }
+        Command::Wallet {
+            action:
+                WalletCommand::Transfer {
+                    from,
+                    to,
+                    amount,
+                    dry_run: false,
+                },
+        } => {
+            Wallet::transfer(&config.wallets_path, from.clone(), to.clone(), amount)?;
+            Ok(format!(
+                "Transferred {} from '{}' to '{}'",
+                amount, from, to
+            ))
+        }
+        Command::Wallet {
+            action:
+                WalletCommand::Adjust {
+                    name,
+                    amount,
+                    op,
+                    dry_run,
+                },
+        } => {
+            let address = wallet_address(config, &name)?;
+            if dry_run {
+                let balance =
+                    Wallet::preview_update_balance(&config.wallets_path, &address, amount, &op)?;
+                return Ok(format!("'{}' would have a balance of {}", name, balance));
+            }
+            let balance = Wallet::update_balance(&config.wallets_path, address, amount, &op)?;
+            Ok(format!("'{}' has a balance of {}", name, balance))
+        }
+        Command::Wallet {
+            action: WalletCommand::History { name },
+        } => {
+            let address = wallet_address(config, &name)?;
+            let history = Transaction::history(&config.blockchain_path, &address);
+            if history.is_empty() {
+                return Ok(format!("No transactions found for '{}'", name));
+            }
+            let lines: Vec<String> = history.iter().map(|t| t.to_string()).collect();
+            Ok(lines.join("\n"))
+        }
+        Command::Wallet {
+            action: WalletCommand::Supply,
+        } => {
+            let supply = Wallet::total_supply(&FileOps {}, &config.wallets_path)
+                .map_err(|e| e.to_string())?;
+            Ok(format!("Total supply of {}", supply))
+        }
+        Command::Wallet {
+            action: WalletCommand::Find { address },
+        } => match Wallet::get_by_address(&FileOps {}, &config.wallets_path, &address) {
+            Ok(Some(wallet)) => Ok(format!(
+                "{} belongs to '{}' with a balance of {}",
+                address, wallet.name, wallet.balance
+            )),
+            Ok(None) => Err(format!("No wallet found with address {}", address)),
+            Err(e) => Err(e.to_string()),
+        },
+        Command::Wallet {
+            action: WalletCommand::Export { out },
+        } => {
+            Wallet::export_csv(&FileOps {}, &config.wallets_path, &out)?;
+            Ok(format!("Wallets exported to {}", out.display()))
+        }
+        Command::Wallet {
+            action: WalletCommand::Import { name, private_key },
+        } => {
+            let wallet = Wallet::import(
+                &config.wallets_path,
+                &config.keypairs_path,
+                name,
+                &private_key,
+            )?;
+            Ok(format!(
+                "Wallet imported for '{}' with address {}",
+                wallet.name, wallet.address
+            ))
+        }
+        Command::Wallet {
+            action: WalletCommand::Freeze { name },
+        } => {
+            Wallet::set_frozen(&config.wallets_path, &name, true)?;
+            Ok(format!("Wallet '{}' frozen", name))
+        }
+        Command::Wallet {
+            action: WalletCommand::Unfreeze { name },
+        } => {
+            Wallet::set_frozen(&config.wallets_path, &name, false)?;
+            Ok(format!("Wallet '{}' unfrozen", name))
+        }
+        Command::Key {
+            action:
+                KeyCommand::Sign {
+                    name,
+                    message,
+                    passphrase,
+                },
+        } => {
+            let key_pair = match passphrase {
+                Some(passphrase) => {
+                    KeyPair::load_decrypted(&config.keypairs_path, &name, &passphrase)?
+                }
+                None => KeyPair::load(&config.keypairs_path, &name)?,
+            };
+            let (signature, _) = KeyPair::sign_recoverable(&message, key_pair.private_key);
+            Ok(signature)
+        }
+        Command::Key {
+            action: KeyCommand::Recover { message, signature },
+        } => {
+            let signature = decode(&signature)
+                .ok()
+                .and_then(|bytes| Signature::from_slice(&bytes).ok())
+                .ok_or_else(|| "Signature is not valid hex encoded ECDSA".to_string())?;
+            let candidates = KeyPair::recover_public_keys(message.as_bytes(), &signature);
+            for public_key in &candidates {
+                if let Some(wallet) =
+                    Wallet::get_by_address(&FileOps {}, &config.wallets_path, public_key)
+                        .map_err(|e| e.to_string())?
+                {
+                    return Ok(format!("Signed by '{}'", wallet.name));
+                }
+            }
+            match candidates.first() {
+                Some(public_key) => Err(format!("Signed by {}, which has no wallet", public_key)),
+                None => Err("No public key could be recovered".to_string()),
+            }
+        }
+        Command::Key {
+            action: KeyCommand::Encrypt { name, passphrase },
+        } => {
+            KeyPair::save_encrypted(&config.keypairs_path, &name, &passphrase)?;
+            Ok(format!(
+                "The private key of '{}' is now encrypted, pass --passphrase to use it",
+                name
+            ))
+        }
+        Command::Key {
+            action: KeyCommand::Backup { name, passphrase },
+        } => {
+            let key_pair = match passphrase {
+                Some(passphrase) => {
+                    KeyPair::load_decrypted(&config.keypairs_path, &name, &passphrase)?
+                }
+                None => KeyPair::load(&config.keypairs_path, &name)?,
+            };
+            Ok(to_mnemonic(&key_pair.private_key).join(" "))
+        }
+        Command::Key {
+            action: KeyCommand::Restore { name, words },
+        } => {
+            let key_pair = mnemonic::restore(name.clone(), &words)?;
+            let wallet = Wallet::import(
+                &config.wallets_path,
+                &config.keypairs_path,
+                name,
+                &key_pair.private_key,
+            )?;
+            Ok(format!(
+                "Wallet restored for '{}' with address {}",
+                wallet.name, wallet.address
+            ))
+        }
+        Command::Key {
+            action: KeyCommand::Address { name },
+        } => Ok(derive_address(&wallet_address(config, &name)?)),
+        Command::Key {
+            action: KeyCommand::Check { address },
+        } => {
+            if validate_address(&address) {
+                Ok(format!("{} is a valid address", address))
+            } else {
+                Err(format!("{} is not a valid address", address))
+            }
+        }
+        Command::Send {
+            from,
+            to,
+            amount,
+            fee,
+            passphrase,
+        } => {
+            if amount <= 0 {
+                return Err("Choose an amount greater than 0".to_string());
+            }
+            let balance = match Wallet::get_balance(&FileOps {}, &config.wallets_path, &from) {
+                Ok(Some(balance)) => balance,
+                Ok(None) => return Err(format!("No wallet found under name '{}'", from)),
+                Err(e) => return Err(e.to_string()),
+            };
+            let cost = Amount::new(amount)?.checked_add(Amount::from(fee))?;
+            if balance < cost.value() {
+                return Err(format!(
+                    "Not enough funds to send {} plus a fee of {} from {}'s account",
+                    amount, fee, from
+                ));
+            }
+            create_transaction(
+                config,
+                from.clone(),
+                to.clone(),
+                amount,
+                fee,
+                passphrase.as_deref(),
+            )?;
+            Ok(format!(
+                "Transaction of {} from '{}' to '{}' added to the mempool",
+                amount, from, to
+            ))
+        }
+        Command::Pending => {
+            FileOps::parse_validated(&config.transactions_path, "transactions")?;
+            Ok(format!(
+                "{} transactions waiting to be mined",
+                mempool::pending_count(&config.transactions_path)
+            ))
+        }
+        Command::Mine { miner } => {
+            mine_block(config, miner.clone())?;
+            Ok(format!(
+                "New block mined successfully. The block reward and fees have been paid to '{}'",
+                miner
+            ))
+        }
+        Command::Chain {
+            action: ChainCommand::Validate,
+        } => match Blockchain::validate_chain(&config.blockchain_path, MIN_DIFFICULTY) {
+            Ok(_) => Ok("VALID CHAIN: true".to_string()),
+            Err(index) => Err(format!("VALID CHAIN: false; block {} is invalid", index)),
+        },
+        Command::Chain {
+            action: ChainCommand::Audit,
+        } => {
+            let issues = Blockchain::audit(&config.blockchain_path, MIN_DIFFICULTY);
+            if issues.is_empty() {
+                return Ok("No problems found in the chain".to_string());
+            }
+            let report: Vec<String> = issues
+                .iter()
+                .map(|issue| format!("block {}: {}", issue.index, issue.description))
+                .collect();
+            Err(format!(
+                "{} problems found in the chain\n{}",
+                issues.len(),
+                report.join("\n")
+            ))
+        }
+        Command::Chain {
+            action: ChainCommand::Proof { height, position },
+        } => {
+            let block = Blockchain::get_block_by_index(&config.blockchain_path, height)
+                .ok_or_else(|| format!("No valid block found at height {}", height))?;
+            let transactions = block.transaction_list();
+            let transaction = transactions
+                .get(position)
+                .ok_or_else(|| format!("Block {} has no transaction at {}", height, position))?;
+            let steps = merkle::proof(&transactions, position);
+            if !merkle::verify_proof(&hash_leaf(transaction), &steps, &block.merkle_root) {
+                return Err(format!(
+                    "Proof does not match the merkle root of block {}",
+                    height
+                ));
+            }
+            let mut lines: Vec<String> = steps
+                .iter()
+                .map(|(sibling, is_right)| {
+                    format!("{} {}", if *is_right { "right" } else { "left" }, sibling)
+                })
+                .collect();
+            lines.push(format!("merkle root {}", block.merkle_root));
+            Ok(lines.join("\n"))
+        }
+        Command::Chain {
+            action: ChainCommand::Block { id },
+        } => {
+            // a 64 character hash is too long to parse as a height
+            let block = match id.parse::<usize>() {
+                Ok(height) => Blockchain::get_block_by_index(&config.blockchain_path, height),
+                Err(_) => Blockchain::get_block_by_hash(&config.blockchain_path, &id),
+            };
+            let block = block.ok_or_else(|| format!("No valid block found for '{}'", id))?;
+            to_string_pretty(&block).map_err(|e| e.to_string())
+        }
+        Command::Chain {
+            action: ChainCommand::Export { out },
+        } => {
+            let (total, added) = export_ndjson(config, &out)?;
+            Ok(format!(
+                "{} blocks exported to {}, {} of them new",
+                total,
+                out.display(),
+                added
+            ))
+        }
+        Command::Chain {
+            action: ChainCommand::Summary,
+        } => {
+            let summary = Blockchain::summary(&config.blockchain_path);
+            to_string_pretty(&summary).map_err(|e| e.to_string())
+        }
+        Command::Chain {
+            action: ChainCommand::Archive { out },
+        } => {
+            let base_data = FileOps::parse_validated(&config.blockchain_path, "blockchain")?;
+            FileOps::write_compressed(&out, &base_data).map_err(|e| e.to_string())?;
+            // read the archive back so a bad write is reported straight away
+            if FileOps::parse_compressed(&out).map_err(|e| e.to_string())? != base_data {
+                return Err(format!(
+                    "Archive at {} does not match the chain",
+                    out.display()
+                ));
+            }
+            Ok(format!(
+                "{} blocks archived to {}",
+                base_data["blockchain"].as_array().map_or(0, Vec::len),
+                out.display()
+            ))
+        }
+        Command::Chain {
+            action: ChainCommand::HashAlgo { algo },
+        } => {
+            // parsed with the names blockchain.json records it under
+            let parsed: HashAlgo = from_value(Value::String(algo.clone())).map_err(|_| {
+                format!(
+                    "Unknown hash algorithm '{}', expected sha256, sha3_256 or blake3",
+                    algo
+                )
+            })?;
+            Blockchain::set_hash_algo(&config.blockchain_path, parsed)?;
+            Ok(format!("Blocks will be hashed with {}", algo))
+        }
+        Command::Snapshot {
+            action: SnapshotCommand::Create { name },
+        } => {
+            FileOps::snapshot(config, &name)?;
+            Ok(format!("Snapshot '{}' saved", name))
+        }
+        Command::Snapshot {
+            action: SnapshotCommand::Restore { name },
+        } => {
+            FileOps::restore(config, &name)?;
+            Ok(format!("Snapshot '{}' restored", name))
+        }
+        Command::Node { addr, peers } => {
+            let (node, handle, synced) = start_node(config, &addr, peers)?;
+            let relay = node.relay_blocks(config);
+            println!(
+                "Node listening on {}; {} of {} peers took the local chain",
+                node.addr,
+                synced,
+                node.peers.len()
+            );
+            let stopped = handle.join();
+            LedgerEvents::unregister(relay);
+            stopped.map_err(|_| "Node stopped unexpectedly".to_string())?;
+            Ok("Node stopped".to_string())
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => {
+            let (bound, handle) = super::server::spawn(&addr, config).map_err(|e| e.to_string())?;
+            println!("Serving the mockchain API on http://{}", bound);
+            handle
+                .join()
+                .map_err(|_| "Server stopped unexpectedly".to_string())?;
+            Ok("Server stopped".to_string())
+        }
+    }
+}
+
+/// Looks up the address of a named wallet
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// name: &str      -> name of the wallet
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, String>
+/// ```
+fn wallet_address(config: &Config, name: &str) -> Result<String, String> {
+    match Wallet::get_wallet_address(&FileOps {}, &config.wallets_path, name) {
+        Ok(Some(address)) => Ok(address),
+        Ok(None) => Err(format!("No wallet found under name '{}'", name)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Brings a newline-delimited copy of the chain up to
+/// date. Blocks already in `out` are kept and only the
+/// rest are appended, unless its last block is no longer
+/// in the chain, in which case the whole chain is copied
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// out: &Path      -> path to the .ndjson file
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(usize, usize), String> -> blocks in the chain and blocks appended
+/// ```
+fn export_ndjson(config: &Config, out: &Path) -> Result<(usize, usize), String> {
+    if !out.exists() {
+        let total = Blockchain::migrate_to_ndjson(&config.blockchain_path, out)?;
+        return Ok((total, total));
+    }
+    let base_data = FileOps::parse_validated(&config.blockchain_path, "blockchain")?;
+    let algo = Blockchain::hash_algo(&config.blockchain_path)?;
+    let chain = base_data["blockchain"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+    let mut exported = 0;
+    let mut last_hash = None;
+    for block in Blockchain::read_blocks_fast(out)? {
+        last_hash = Some(block?.hash);
+        exported += 1;
+    }
+    let linked = match last_hash {
+        Some(hash) => exported <= chain.len() && chain[exported - 1]["hash"] == hash,
+        None => true,
+    };
+    if !linked {
+        let total = Blockchain::migrate_to_ndjson(&config.blockchain_path, out)?;
+        return Ok((total, total));
+    }
+    for (i, block) in chain.iter().enumerate().skip(exported) {
+        Blockchain::append_block_fast(out, &Block::from_chain_value(block, i, algo)?)?;
+    }
+    Ok((chain.len(), chain.len() - exported))
+}
+
+/// Starts a p2p node in the background and sends the
+/// local chain to its peers so any that are behind
+/// catch up
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config        -> locations of the data files
+/// addr: &str             -> address to listen on
+/// peers: Vec<SocketAddr> -> nodes to broadcast to
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(Node, JoinHandle<()>, usize), String> -> the node, its thread and the peers that took the chain
+/// ```
+fn start_node(
+    config: &Config,
+    addr: &str,
+    peers: Vec<SocketAddr>,
+) -> Result<(Node, JoinHandle<()>, usize), String> {
+    let base_data = FileOps::parse_validated(&config.blockchain_path, "blockchain")?;
+    let algo = Blockchain::hash_algo(&config.blockchain_path)?;
+    let chain = base_data["blockchain"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice)
+        .iter()
+        .enumerate()
+        .map(|(i, block)| Block::from_chain_value(block, i, algo))
+        .collect::<Result<Vec<Block>, String>>()?;
+    let (node, handle) = Node::start(addr, config, peers).map_err(|e| e.to_string())?;
+    let synced = node.broadcast_chain(&chain);
+    Ok((node, handle, synced))
+}
+
+// Testing
+#[cfg(test)]
+mod test_cli {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use crate::mods::{block::BLOCK_REWARD, log::Log};
+
+    fn run(args: &[&str], data_dir: &Path) -> Result<String, String> {
+        let data_dir = data_dir.to_str().unwrap();
+        let args = ["mockchain_v2", "--data-dir", data_dir]
+            .into_iter()
+            .chain(args.iter().copied());
+        let cli = Cli::try_parse_from(args).map_err(|e| e.to_string())?;
+        dispatch(cli.command.unwrap(), &Config::load(cli.data_dir))
+    }
+
+    fn balance_of(data_dir: &Path, name: &str) -> Option<i64> {
+        Wallet::get_balance(&FileOps {}, &data_dir.join("wallets.json"), name).unwrap()
+    }
+
+    /// initialises a data directory holding alice and bob, with
+    /// one block mined by alice
+    fn setup(dir_name: &str) -> PathBuf {
+        Log::init();
+        let data_dir = temp_dir().join(dir_name);
+        FileOps::init(&Config::new(&data_dir), false);
+        run(&["wallet", "create", "alice"], &data_dir).unwrap();
+        run(&["wallet", "create", "bob"], &data_dir).unwrap();
+        run(&["mine", "alice"], &data_dir).unwrap();
+        data_dir
+    }
+
+    #[test]
+    fn test_dispatch() {
+        Log::init();
+        let data_dir = temp_dir().join("mockchain_test_cli");
+        FileOps::init(&Config::new(&data_dir), false);
+
+        run(&["wallet", "create", "alice"], &data_dir).unwrap();
+        run(&["wallet", "create", "bob"], &data_dir).unwrap();
+        let duplicate = run(&["wallet", "create", "bob"], &data_dir);
+        run(&["mine", "alice"], &data_dir).unwrap();
+        run(&["send", "alice", "bob", "20", "--fee", "2"], &data_dir).unwrap();
+        let overdraft = run(&["send", "alice", "bob", "1000"], &data_dir);
+        let pending = run(&["pending"], &data_dir);
+        run(&["mine", "bob"], &data_dir).unwrap();
+        let balance = run(&["wallet", "balance", "alice"], &data_dir);
+        let missing = run(&["wallet", "balance", "carol"], &data_dir);
+        let valid = run(&["chain", "validate"], &data_dir);
+        let audit = run(&["chain", "audit"], &data_dir);
+        let unknown = run(&["burn", "alice"], &data_dir);
+
+        let alice = balance_of(&data_dir, "alice");
+        let bob = balance_of(&data_dir, "bob");
+        let supply = Wallet::total_supply(&FileOps {}, &data_dir.join("wallets.json")).unwrap();
+        let blocks = FileOps::parse(&data_dir.join("blockchain.json")).unwrap();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(duplicate.is_err());
+        assert!(overdraft.is_err());
+        assert_eq!(
+            Ok("1 transactions waiting to be mined".to_string()),
+            pending
+        );
+        assert_eq!(Some(28), alice);
+        assert_eq!(Some(72), bob);
+        // fees move between wallets so only the two block rewards add supply
+        assert_eq!(2 * BLOCK_REWARD as i64, supply);
+        assert_eq!(Ok("'alice' has a balance of 28".to_string()), balance);
+        assert!(missing.is_err());
+        assert!(valid.is_ok());
+        assert_eq!(Ok("No problems found in the chain".to_string()), audit);
+        assert!(unknown.is_err());
+        assert_eq!(3, blocks["blockchain"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_wallet_create_many() {
+        let data_dir = setup("mockchain_test_cli_create_many");
+
+        let created = run(&["wallet", "create", "carol", "dave"], &data_dir).unwrap();
+        let taken = run(&["wallet", "create", "erin", "alice"], &data_dir);
+        let repeated = run(&["wallet", "create", "frank", "frank"], &data_dir);
+        let listed = run(&["wallet", "list"], &data_dir);
+        let none = run(&["wallet", "create"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(2, created.lines().count());
+        assert!(taken.is_err());
+        assert!(repeated.is_err());
+        // a rejected batch creates none of its wallets
+        assert_eq!(
+            Ok("alice: 50\nbob: 0\ncarol: 0\ndave: 0".to_string()),
+            listed
+        );
+        assert!(none.is_err());
+    }
+
+    #[test]
+    fn test_wallet_balances() {
+        let data_dir = setup("mockchain_test_cli_balances");
+
+        let both = run(&["wallet", "balance", "alice", "bob"], &data_dir);
+        let missing = run(&["wallet", "balance", "alice", "carol"], &data_dir);
+        let at = run(
+            &["wallet", "balance", "bob", "alice", "--at", "1"],
+            &data_dir,
+        );
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok("'alice' has a balance of 50\n'bob' has a balance of 0".to_string()),
+            both
+        );
+        assert_eq!(
+            Err("No wallet found under name 'carol'".to_string()),
+            missing
+        );
+        assert_eq!(
+            Ok(
+                "'bob' had a balance of 0 at block 1\n'alice' had a balance of 50 at block 1"
+                    .to_string()
+            ),
+            at
+        );
+    }
+
+    #[test]
+    fn test_wallet_balance_at() {
+        let data_dir = setup("mockchain_test_cli_balance_at");
+        run(&["send", "alice", "bob", "5", "--fee", "1"], &data_dir).unwrap();
+        run(&["mine", "bob"], &data_dir).unwrap();
+
+        let before = run(&["wallet", "balance", "alice", "--at", "1"], &data_dir);
+        let after = run(&["wallet", "balance", "alice", "--at", "2"], &data_dir);
+        let genesis = run(&["wallet", "balance", "bob", "--at", "0"], &data_dir);
+        let beyond = run(&["wallet", "balance", "bob", "--at", "3"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok("'alice' had a balance of 50 at block 1".to_string()),
+            before
+        );
+        assert_eq!(
+            Ok("'alice' had a balance of 44 at block 2".to_string()),
+            after
+        );
+        assert_eq!(
+            Ok("'bob' had a balance of 0 at block 0".to_string()),
+            genesis
+        );
+        assert!(beyond.is_err());
+    }
+
+    #[test]
+    fn test_wallet_transfer() {
+        let data_dir = setup("mockchain_test_cli_transfer");
+
+        let transferred = run(&["wallet", "transfer", "alice", "bob", "20"], &data_dir);
+        let overdraft = run(&["wallet", "transfer", "alice", "bob", "100"], &data_dir);
+        let alice = balance_of(&data_dir, "alice");
+        let bob = balance_of(&data_dir, "bob");
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok("Transferred 20 from 'alice' to 'bob'".to_string()),
+            transferred
+        );
+        assert!(overdraft.is_err());
+        assert_eq!(Some(30), alice);
+        assert_eq!(Some(20), bob);
+    }
+
+    #[test]
+    fn test_wallet_adjust() {
+        let data_dir = setup("mockchain_test_cli_adjust");
+
+        let added = run(&["wallet", "adjust", "bob", "15", "add"], &data_dir);
+        let subtracted = run(&["wallet", "adjust", "alice", "10", "subtract"], &data_dir);
+        let overdraft = run(&["wallet", "adjust", "bob", "100", "subtract"], &data_dir);
+        let bad_op = run(&["wallet", "adjust", "bob", "1", "double"], &data_dir);
+        let missing = run(&["wallet", "adjust", "carol", "1", "add"], &data_dir);
+        let alice = balance_of(&data_dir, "alice");
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("'bob' has a balance of 15".to_string()), added);
+        assert_eq!(Ok("'alice' has a balance of 40".to_string()), subtracted);
+        assert!(overdraft.is_err());
+        assert!(bad_op.is_err());
+        assert!(missing.is_err());
+        assert_eq!(Some(40), alice);
+    }
+
+    #[test]
+    fn test_wallet_history() {
+        let data_dir = setup("mockchain_test_cli_history");
+        run(&["wallet", "create", "carol"], &data_dir).unwrap();
+        run(&["send", "alice", "bob", "5"], &data_dir).unwrap();
+        run(&["mine", "alice"], &data_dir).unwrap();
+
+        let alice = run(&["wallet", "history", "alice"], &data_dir).unwrap();
+        let bob = run(&["wallet", "history", "bob"], &data_dir).unwrap();
+        let carol = run(&["wallet", "history", "carol"], &data_dir);
+        let missing = run(&["wallet", "history", "dave"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        // alice was paid two coinbases and sent one payment
+        assert_eq!(3, alice.lines().count());
+        assert_eq!(1, bob.lines().count());
+        assert_eq!(Ok("No transactions found for 'carol'".to_string()), carol);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_wallet_supply() {
+        let data_dir = setup("mockchain_test_cli_supply");
+        run(&["send", "alice", "bob", "5", "--fee", "1"], &data_dir).unwrap();
+        run(&["mine", "bob"], &data_dir).unwrap();
+
+        let supply = run(&["wallet", "supply"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok(format!("Total supply of {}", 2 * BLOCK_REWARD)), supply);
+    }
+
+    #[test]
+    fn test_wallet_dry_run() {
+        let data_dir = setup("mockchain_test_cli_dry_run");
+
+        let transfer = run(
+            &["wallet", "transfer", "alice", "bob", "20", "--dry-run"],
+            &data_dir,
+        );
+        let adjust = run(
+            &["wallet", "adjust", "bob", "15", "add", "--dry-run"],
+            &data_dir,
+        );
+        let overdraft = run(
+            &["wallet", "transfer", "alice", "bob", "100", "--dry-run"],
+            &data_dir,
+        );
+        let alice = balance_of(&data_dir, "alice");
+        let bob = balance_of(&data_dir, "bob");
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok("Transferring 20 would leave 'alice' with 30 and 'bob' with 20".to_string()),
+            transfer
+        );
+        assert_eq!(Ok("'bob' would have a balance of 15".to_string()), adjust);
+        assert!(overdraft.is_err());
+        assert_eq!(Some(50), alice);
+        assert_eq!(Some(0), bob);
+    }
+
+    #[test]
+    fn test_wallet_find() {
+        let data_dir = setup("mockchain_test_cli_find");
+        let address =
+            Wallet::get_wallet_address(&FileOps {}, &data_dir.join("wallets.json"), "alice")
+                .unwrap()
+                .unwrap();
+
+        let found = run(&["wallet", "find", &address], &data_dir);
+        let missing = run(&["wallet", "find", "abc"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok(format!(
+                "{} belongs to 'alice' with a balance of 50",
+                address
+            )),
+            found
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_wallet_export() {
+        let data_dir = setup("mockchain_test_cli_wallet_export");
+        let out = data_dir.join("wallets.csv");
+
+        let exported = run(&["wallet", "export", out.to_str().unwrap()], &data_dir);
+        let csv = fs::read_to_string(&out).unwrap();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(exported.is_ok());
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(3, rows.len());
+        assert_eq!("name,address,balance", rows[0]);
+        assert!(rows[1].starts_with("alice,") && rows[1].ends_with(",50"));
+    }
+
+    #[test]
+    fn test_wallet_freeze() {
+        let data_dir = setup("mockchain_test_cli_freeze");
+
+        let frozen = run(&["wallet", "freeze", "bob"], &data_dir);
+        let listed = run(&["wallet", "list"], &data_dir);
+        let received = run(&["wallet", "transfer", "alice", "bob", "5"], &data_dir);
+        let blocked = run(&["wallet", "transfer", "bob", "alice", "5"], &data_dir);
+        let unfrozen = run(&["wallet", "unfreeze", "bob"], &data_dir);
+        let sent = run(&["wallet", "transfer", "bob", "alice", "5"], &data_dir);
+        let missing = run(&["wallet", "freeze", "carol"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("Wallet 'bob' frozen".to_string()), frozen);
+        assert_eq!(Ok("alice: 50\nbob: 0 (frozen)".to_string()), listed);
+        assert!(received.is_ok());
+        assert!(blocked.is_err());
+        assert_eq!(Ok("Wallet 'bob' unfrozen".to_string()), unfrozen);
+        assert!(sent.is_ok());
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_wallet_import() {
+        let data_dir = setup("mockchain_test_cli_import");
+        let other_dir = temp_dir().join("mockchain_test_cli_import_other");
+        FileOps::init(&Config::new(&other_dir), false);
+        let key_pair = KeyPair::load(&data_dir.join("keypairs.json"), "alice").unwrap();
+
+        let imported = run(
+            &["wallet", "import", "alice", &key_pair.private_key],
+            &other_dir,
+        );
+        let again = run(
+            &["wallet", "import", "carol", &key_pair.private_key],
+            &other_dir,
+        );
+        let invalid = run(&["wallet", "import", "dave", "xyz"], &other_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+        fs::remove_dir_all(&other_dir).unwrap();
+
+        assert_eq!(
+            Ok(format!(
+                "Wallet imported for 'alice' with address {}",
+                key_pair.public_key
+            )),
+            imported
+        );
+        // the address is already taken by the first import
+        assert!(again.is_err());
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_wallet_list() {
+        let data_dir = setup("mockchain_test_cli_list");
+
+        let listed = run(&["wallet", "list"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("alice: 50\nbob: 0".to_string()), listed);
+    }
+
+    #[test]
+    fn test_wallet_delete() {
+        let data_dir = setup("mockchain_test_cli_delete");
+
+        let deleted = run(&["wallet", "delete", "bob"], &data_dir);
+        let again = run(&["wallet", "delete", "bob"], &data_dir);
+        let listed = run(&["wallet", "list"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("Wallet 'bob' deleted".to_string()), deleted);
+        assert!(again.is_err());
+        assert_eq!(Ok("alice: 50".to_string()), listed);
+    }
+
+    #[test]
+    fn test_wallet_rename() {
+        let data_dir = setup("mockchain_test_cli_rename");
+
+        let renamed = run(&["wallet", "rename", "bob", "carol"], &data_dir);
+        let taken = run(&["wallet", "rename", "carol", "alice"], &data_dir);
+        let carol = balance_of(&data_dir, "carol");
+        let bob = balance_of(&data_dir, "bob");
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(renamed.is_ok());
+        assert!(taken.is_err());
+        assert_eq!(Some(0), carol);
+        assert_eq!(None, bob);
+    }
+
+    #[test]
+    fn test_key_sign_recover() {
+        let data_dir = setup("mockchain_test_cli_sign");
+
+        let signed = run(&["key", "sign", "alice", "hello"], &data_dir).unwrap();
+        let recovered = run(&["key", "recover", "hello", &signed], &data_dir);
+        let other_message = run(&["key", "recover", "goodbye", &signed], &data_dir);
+        let malformed = run(&["key", "recover", "hello", "xyz"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("Signed by 'alice'".to_string()), recovered);
+        assert!(other_message.is_err());
+        assert!(malformed.is_err());
+    }
+
+    #[test]
+    fn test_key_encrypt() {
+        let data_dir = setup("mockchain_test_cli_encrypt");
+
+        run(&["key", "encrypt", "alice", "correct horse"], &data_dir).unwrap();
+        let locked = run(&["send", "alice", "bob", "5"], &data_dir);
+        let wrong = run(
+            &[
+                "send",
+                "alice",
+                "bob",
+                "5",
+                "--passphrase",
+                "battery staple",
+            ],
+            &data_dir,
+        );
+        let sent = run(
+            &["send", "alice", "bob", "5", "--passphrase", "correct horse"],
+            &data_dir,
+        );
+        let keypairs = FileOps::parse(&data_dir.join("keypairs.json")).unwrap();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(locked.unwrap_err().contains("passphrase is needed"));
+        assert!(wrong.is_err());
+        assert!(sent.is_ok());
+        assert!(keypairs["keypairs"][0].get("private_key").is_none());
+    }
+
+    #[test]
+    fn test_key_backup_restore() {
+        let data_dir = setup("mockchain_test_cli_backup");
+        let other_dir = temp_dir().join("mockchain_test_cli_backup_restored");
+        FileOps::init(&Config::new(&other_dir), false);
+
+        let backup = run(&["key", "backup", "alice"], &data_dir).unwrap();
+        let words: Vec<&str> = backup.split(' ').collect();
+        let restored = run(
+            &[&["key", "restore", "alice"][..], &words].concat(),
+            &other_dir,
+        );
+        let typo = run(
+            &[&["key", "restore", "carol", "abandon"][..], &words[1..]].concat(),
+            &other_dir,
+        );
+        let original =
+            Wallet::get_wallet_address(&FileOps {}, &data_dir.join("wallets.json"), "alice");
+        let copy =
+            Wallet::get_wallet_address(&FileOps {}, &other_dir.join("wallets.json"), "alice");
+        fs::remove_dir_all(&data_dir).unwrap();
+        fs::remove_dir_all(&other_dir).unwrap();
+
+        assert_eq!(24, words.len());
+        assert!(restored.is_ok());
+        assert!(typo.is_err());
+        assert_eq!(original.unwrap(), copy.unwrap());
+    }
+
+    #[test]
+    fn test_key_address() {
+        let data_dir = setup("mockchain_test_cli_address");
+
+        let address = run(&["key", "address", "alice"], &data_dir).unwrap();
+        let valid = run(&["key", "check", &address], &data_dir);
+        let mut typo = address.clone();
+        let last = if typo.pop() == Some('2') { '3' } else { '2' };
+        typo.push(last);
+        let invalid = run(&["key", "check", &typo], &data_dir);
+        let missing = run(&["key", "address", "carol"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok(format!("{} is a valid address", address)), valid);
+        assert!(invalid.is_err());
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_chain_proof() {
+        let data_dir = setup("mockchain_test_cli_proof");
+        run(&["send", "alice", "bob", "5"], &data_dir).unwrap();
+        run(&["send", "alice", "bob", "6"], &data_dir).unwrap();
+        run(&["mine", "bob"], &data_dir).unwrap();
+
+        let proof = run(&["chain", "proof", "2", "1"], &data_dir).unwrap();
+        let missing = run(&["chain", "proof", "2", "3"], &data_dir);
+        let block = Blockchain::get_block_by_index(&data_dir.join("blockchain.json"), 2);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        // a coinbase and two payments need two levels of siblings
+        let lines: Vec<&str> = proof.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("left "));
+        assert_eq!(
+            format!("merkle root {}", block.unwrap().merkle_root),
+            lines[2]
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_chain_block() {
+        let data_dir = setup("mockchain_test_cli_block");
+
+        let by_height = run(&["chain", "block", "1"], &data_dir).unwrap();
+        let block: Block = serde_json::from_str(&by_height).unwrap();
+        let by_hash = run(&["chain", "block", &block.hash], &data_dir);
+        let missing_height = run(&["chain", "block", "5"], &data_dir);
+        let missing_hash = run(&["chain", "block", &"f".repeat(64)], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok(by_height), by_hash);
+        assert!(missing_height.is_err());
+        assert!(missing_hash.is_err());
+    }
+
+    #[test]
+    fn test_chain_export() {
+        let data_dir = setup("mockchain_test_cli_export");
+        let out = data_dir.join("blockchain.ndjson");
+        let out_arg = out.to_str().unwrap();
+
+        let first = run(&["chain", "export", out_arg], &data_dir);
+        run(&["mine", "bob"], &data_dir).unwrap();
+        let second = run(&["chain", "export", out_arg], &data_dir);
+        let unchanged = run(&["chain", "export", out_arg], &data_dir);
+        let exported: Vec<Block> = Blockchain::read_blocks_fast(&out)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let tip = Blockchain::get_block_by_index(&data_dir.join("blockchain.json"), 2);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        let message = |total, added| {
+            Ok(format!(
+                "{} blocks exported to {}, {} of them new",
+                total,
+                out.display(),
+                added
+            ))
+        };
+        assert_eq!(message(2, 2), first);
+        assert_eq!(message(3, 1), second);
+        assert_eq!(message(3, 0), unchanged);
+        assert_eq!(3, exported.len());
+        assert_eq!(tip.as_ref(), exported.last());
+    }
+
+    #[test]
+    fn test_chain_summary() {
+        let data_dir = setup("mockchain_test_cli_summary");
+
+        let summary = run(&["chain", "summary"], &data_dir).unwrap();
+        let tip = Blockchain::get_block_by_index(&data_dir.join("blockchain.json"), 1);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+        assert_eq!(2, summary["blocks"]);
+        assert_eq!(1, summary["transactions"]);
+        assert_eq!(tip.unwrap().hash, summary["tip_hash"]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let data_dir = setup("mockchain_test_cli_snapshot");
+
+        let saved = run(&["snapshot", "create", "before"], &data_dir);
+        run(&["wallet", "transfer", "alice", "bob", "20"], &data_dir).unwrap();
+        run(&["mine", "bob"], &data_dir).unwrap();
+        let restored = run(&["snapshot", "restore", "before"], &data_dir);
+        let missing = run(&["snapshot", "restore", "after"], &data_dir);
+        let alice = balance_of(&data_dir, "alice");
+        let summary = Blockchain::summary(&data_dir.join("blockchain.json"));
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(Ok("Snapshot 'before' saved".to_string()), saved);
+        assert_eq!(Ok("Snapshot 'before' restored".to_string()), restored);
+        assert!(missing.is_err());
+        assert_eq!(Some(50), alice);
+        assert_eq!(2, summary.blocks);
+    }
+
+    #[test]
+    fn test_chain_archive() {
+        let data_dir = setup("mockchain_test_cli_archive");
+        let out = data_dir.join("blockchain.json.gz");
+
+        let archived = run(&["chain", "archive", out.to_str().unwrap()], &data_dir);
+        let chain = FileOps::parse(&data_dir.join("blockchain.json")).unwrap();
+        let archive = FileOps::parse_compressed(&out).unwrap();
+        // gzip output does not parse as plain JSON
+        let plain = FileOps::parse(&out);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert_eq!(
+            Ok(format!("2 blocks archived to {}", out.display())),
+            archived
+        );
+        assert_eq!(chain, archive);
+        assert!(plain.is_err());
+    }
+
+    #[test]
+    fn test_chain_hash_algo() {
+        Log::init();
+        let data_dir = temp_dir().join("mockchain_test_cli_hash_algo");
+        FileOps::init(&Config::new(&data_dir), false);
+        let blockchain_path = data_dir.join("blockchain.json");
+
+        let unknown = run(&["chain", "hash-algo", "md5"], &data_dir);
+        let set = run(&["chain", "hash-algo", "blake3"], &data_dir);
+        let algo = Blockchain::hash_algo(&blockchain_path);
+        run(&["wallet", "create", "alice"], &data_dir).unwrap();
+        run(&["mine", "alice"], &data_dir).unwrap();
+        let valid = run(&["chain", "validate"], &data_dir);
+        let too_late = run(&["chain", "hash-algo", "sha256"], &data_dir);
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(unknown.is_err());
+        assert_eq!(Ok("Blocks will be hashed with blake3".to_string()), set);
+        assert_eq!(Ok(HashAlgo::Blake3), algo);
+        assert!(valid.is_ok());
+        assert!(too_late.is_err());
+    }
+
+    #[test]
+    fn test_start_node() {
+        let ahead = setup("mockchain_test_cli_node_ahead");
+        let behind = temp_dir().join("mockchain_test_cli_node_behind");
+        FileOps::init(&Config::new(&behind), false);
+
+        let (receiver, _, _) = start_node(&Config::new(&behind), "127.0.0.1:0", vec![]).unwrap();
+        let (_, _, synced) =
+            start_node(&Config::new(&ahead), "127.0.0.1:0", vec![receiver.addr]).unwrap();
+        let tip = Blockchain::get_block_by_index(&ahead.join("blockchain.json"), 1);
+        let received = Blockchain::get_block_by_index(&behind.join("blockchain.json"), 1);
+        fs::remove_dir_all(&ahead).unwrap();
+        fs::remove_dir_all(&behind).unwrap();
+
+        assert_eq!(1, synced);
+        assert_eq!(tip, received);
+    }
+
+    #[test]
+    fn test_dispatch_before_init() {
+        let data_dir = temp_dir().join("mockchain_test_cli_uninitialised");
+
+        let balance = run(&["wallet", "balance", "alice"], &data_dir);
+        let send = run(&["send", "alice", "bob", "5"], &data_dir);
+        let mine = run(&["mine", "alice"], &data_dir);
+        let pending = run(&["pending"], &data_dir);
+
+        for result in [balance, send, mine, pending] {
+            assert!(result.unwrap_err().starts_with("No data file found"));
+        }
+        assert!(!data_dir.exists());
+    }
+}
@@ -0,0 +1,164 @@
+/*
+    Locations of the data files so independent chains
+    can live in separate directories, along with the
+    settings used when mining into them
+*/
+
+// std library
+use std::env;
+use std::path::{Path, PathBuf};
+
+// imports
+use super::block::MAX_TXS_PER_BLOCK;
+use crate::{
+    BLOCKCHAIN_PATH, DATA_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH, WALLETS_PATH,
+};
+
+/// Environment variable that overrides the default
+/// data directory
+pub const DATA_DIR_ENV: &str = "MOCKCHAIN_DATA_DIR";
+
+/// Defines the data directory, the data files held
+/// inside it and the mining settings for the chain
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// data_dir: PathBuf          -> directory holding the data files
+/// blockchain_path: PathBuf   -> path to blockchain.json
+/// transactions_path: PathBuf -> path to transactions.json
+/// wallets_path: PathBuf      -> path to wallets.json
+/// keypairs_path: PathBuf     -> path to keypairs.json
+/// signing_data_path: PathBuf -> path to signing.json
+/// max_txs_per_block: usize   -> most mempool transactions mined into one block
+/// ```
+///
+/// # Derives
+/// ```
+/// Clone, Debug
+/// ```
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub data_dir: PathBuf,
+    pub blockchain_path: PathBuf,
+    pub transactions_path: PathBuf,
+    pub wallets_path: PathBuf,
+    pub keypairs_path: PathBuf,
+    pub signing_data_path: PathBuf,
+    pub max_txs_per_block: usize,
+}
+
+impl Config {
+    /// Creates a Config for the data files under
+    /// `data_dir`
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data_dir: &Path -> directory holding the data files
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Config
+    /// ```
+    pub fn new(data_dir: &Path) -> Config {
+        Config {
+            data_dir: data_dir.to_path_buf(),
+            blockchain_path: data_dir.join("blockchain.json"),
+            transactions_path: data_dir.join("transactions.json"),
+            wallets_path: data_dir.join("wallets.json"),
+            keypairs_path: data_dir.join("keypairs.json"),
+            signing_data_path: data_dir.join("signing.json"),
+            max_txs_per_block: MAX_TXS_PER_BLOCK,
+        }
+    }
+
+    /// Picks the data directory from the passed argument,
+    /// then MOCKCHAIN_DATA_DIR, then ~/.mockchain/data
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data_dir: Option<PathBuf> -> directory given on the command line
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Config
+    /// ```
+    pub fn load(data_dir: Option<PathBuf>) -> Config {
+        match data_dir.or_else(|| env::var_os(DATA_DIR_ENV).map(PathBuf::from)) {
+            Some(dir) => Config::new(&dir),
+            None => Config::default(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            data_dir: DATA_PATH.clone(),
+            blockchain_path: BLOCKCHAIN_PATH.clone(),
+            transactions_path: TRANSACTIONS_PATH.clone(),
+            wallets_path: WALLETS_PATH.clone(),
+            keypairs_path: KEYPAIRS_PATH.clone(),
+            signing_data_path: SIGNING_DATA_PATH.clone(),
+            max_txs_per_block: MAX_TXS_PER_BLOCK,
+        }
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_config {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use crate::mods::{file::FileOps, wallet::Wallet};
+
+    #[test]
+    fn test_load_argument() {
+        let dir = temp_dir().join("mockchain_test_config_load");
+        let config = Config::load(Some(dir.clone()));
+
+        assert_eq!(dir, config.data_dir);
+        assert_eq!(dir.join("wallets.json"), config.wallets_path);
+        assert_eq!(
+            DATA_PATH.join("blockchain.json"),
+            Config::default().blockchain_path
+        );
+    }
+
+    #[test]
+    fn test_isolated_data_dirs() {
+        let first = Config::new(&temp_dir().join("mockchain_test_config_first"));
+        let second = Config::new(&temp_dir().join("mockchain_test_config_second"));
+        FileOps::init(&first, false);
+        FileOps::init(&second, false);
+
+        Wallet::new(
+            &first.wallets_path,
+            &first.keypairs_path,
+            String::from("alice"),
+        )
+        .unwrap();
+        let in_first = Wallet::name_exists(&FileOps {}, &first.wallets_path, "alice").unwrap();
+        let in_second = Wallet::name_exists(&FileOps {}, &second.wallets_path, "alice").unwrap();
+        let keypairs = FileOps::parse(&second.keypairs_path).unwrap();
+        fs::remove_dir_all(&first.data_dir).unwrap();
+        fs::remove_dir_all(&second.data_dir).unwrap();
+
+        assert!(in_first);
+        assert!(!in_second);
+        assert!(keypairs["keypairs"].as_array().unwrap().is_empty());
+    }
+}
@@ -3,6 +3,12 @@ use std::path::Path;
 use std::str;
 
 // 3rd party crates
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ecdsa::RecoveryId;
 use hex::{decode, encode};
 use p256::{
     ecdsa::{
@@ -11,22 +17,78 @@ use p256::{
     },
     SecretKey,
 };
-use rand_core::OsRng;
-use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
-use serde::Serialize;
+use pbkdf2::pbkdf2_hmac;
+use rand_core::{OsRng, RngCore};
+use ripemd::Ripemd160;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, to_value};
+use sha2::{Digest, Sha256};
 use sha256::digest;
+use sha3::Sha3_256;
 
 // imports
 use super::{
     file::FileOps,
     log::{Log, LogLevel},
 };
-use crate::KEYPAIRS_PATH;
 
 // hash delimiter
 #[allow(dead_code)]
 pub const DELIMITER: &str = "-%-";
 
+// PBKDF2 rounds used to derive private key encryption keys
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+/// Hash functions a chain can use for its block
+/// hashes. SHA-256 is the default
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// Sha256   -> SHA-256
+/// Sha3_256 -> SHA3-256
+/// Blake3   -> BLAKE3 with a 32 byte output
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgo {
+    #[default]
+    Sha256,
+    Sha3_256,
+    Blake3,
+}
+
+impl HashAlgo {
+    /// Hashes a string with this algorithm
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data: &str -> data to hash
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String -> lowercase hex digest
+    /// ```
+    pub fn digest(&self, data: &str) -> String {
+        match self {
+            HashAlgo::Sha256 => digest(data),
+            HashAlgo::Sha3_256 => encode(Sha3_256::digest(data.as_bytes())),
+            HashAlgo::Blake3 => blake3::hash(data.as_bytes()).to_hex().to_string(),
+        }
+    }
+}
+
 /// Defines a KeyPair object for storing private and public keys
 ///
 /// # Visibility
@@ -41,15 +103,38 @@ pub const DELIMITER: &str = "-%-";
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct KeyPair {
     pub name: String,
     pub public_key: String,
     pub private_key: String,
 }
 
+/// Defines a private key encrypted with a passphrase
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// ciphertext: String -> AES-GCM encrypted private key bytes (base64)
+/// nonce: String      -> AES-GCM nonce (base64)
+/// salt: String       -> PBKDF2 salt (base64)
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug
+/// ```
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedKey {
+    pub ciphertext: String,
+    pub nonce: String,
+    pub salt: String,
+}
+
 impl KeyPair {
     /// Creates a new key pair including a public
     /// and private key
@@ -67,53 +152,276 @@ impl KeyPair {
     /// KeyPair
     /// ```
     pub fn generate(name: String) -> KeyPair {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        KeyPair::from_seed(name, &seed)
+    }
+
+    /// Deterministically creates a key pair from a seed
+    /// so the same seed always restores the same keys
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: String    -> name of the account for this key pair
+    /// seed: &[u8; 32] -> seed to derive the private key from
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// KeyPair
+    /// ```
+    pub fn from_seed(name: String, seed: &[u8; 32]) -> KeyPair {
+        // a seed that is not a valid scalar is re-hashed until it is
+        let mut bytes = *seed;
+        let secret = loop {
+            match SecretKey::from_slice(&bytes) {
+                Ok(secret) => break secret,
+                Err(_) => bytes = Sha256::digest(bytes).into(),
+            }
+        };
         // private key first
-        let secret = SecretKey::random(&mut OsRng);
-        let private_key = encode(&secret.to_bytes());
+        let private_key = encode(secret.to_bytes());
         // then public key
         let public_key = encode(secret.public_key().to_sec1_bytes());
-        let key_pair = KeyPair {
+        KeyPair {
             name,
             public_key,
             private_key,
+        }
+    }
+
+    /// Rebuilds a key pair from an existing hex encoded
+    /// private key, deriving the public key from it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: String       -> name of the account for this key pair
+    /// private_key: &str  -> hex encoded private key
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<KeyPair, String>
+    /// ```
+    pub fn from_private_key(name: String, private_key: &str) -> Result<KeyPair, String> {
+        let bytes = decode(private_key.trim())
+            .map_err(|e| format!("Private key is not valid hex: {}", e))?;
+        let secret = SecretKey::from_slice(&bytes)
+            .map_err(|_| "Private key is not a valid P-256 secret key".to_string())?;
+        Ok(KeyPair {
+            name,
+            public_key: encode(secret.public_key().to_sec1_bytes()),
+            private_key: encode(secret.to_bytes()),
+        })
+    }
+
+    /// Loads a key pair from keypairs.json and checks the
+    /// stored public key is the one derived from the stored
+    /// private key, so a corrupted or swapped entry is
+    /// caught before it signs anything
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to keypairs.json
+    /// name: &str  -> name of the account for this key pair
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<KeyPair, String>
+    /// ```
+    pub fn load(path: &Path, name: &str) -> Result<KeyPair, String> {
+        let base_data = FileOps::parse_validated(path, "keypairs")?;
+        let key_pair = match base_data["keypairs"]
+            .as_array()
+            .and_then(|keypairs| keypairs.iter().find(|k| k["name"] == *name))
+        {
+            Some(k) => k,
+            None => return Err(format!("No key pair found under {}", name)),
         };
-        key_pair
+        let (public_key, private_key) = match (
+            key_pair["public_key"].as_str(),
+            key_pair["private_key"].as_str(),
+        ) {
+            (Some(public), Some(private)) => (public, private),
+            (Some(_), None) if key_pair.get("encrypted_private_key").is_some() => {
+                return Err(format!(
+                    "The private key of {} is encrypted, a passphrase is needed",
+                    name
+                ))
+            }
+            _ => return Err(format!("Incomplete key pair stored under {}", name)),
+        };
+
+        let derived = KeyPair::from_private_key(name.to_string(), private_key)
+            .ok()
+            .map(|key_pair| key_pair.public_key);
+        if derived.as_deref() != Some(public_key) {
+            return Err(format!(
+                "Key pair under {} is corrupt, the public key does not match the private key",
+                name
+            ));
+        }
+        Ok(KeyPair {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+            private_key: private_key.to_string(),
+        })
     }
 
-    /// Gets a key from keypairs.json file
+    /// Encrypts the private key of a key pair stored in
+    /// keypairs.json with a passphrase. The plaintext
+    /// private key is removed from the file and replaced
+    /// by the ciphertext, nonce, and salt
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: String -> name of account to get key from
-    /// key: String  -> ["public" | "private"]
+    /// path: &Path      -> path to keypairs.json
+    /// name: &str       -> name of the account for this key pair
+    /// passphrase: &str -> passphrase to derive the encryption key from
     /// ```
     ///
     /// # Returns
     /// ```
-    /// String
+    /// Result<(), String>
     /// ```
-    pub fn get_key(name: String, key: String) -> String {
-        let mut base_data = FileOps::parse(KEYPAIRS_PATH.as_path());
+    pub fn save_encrypted(path: &Path, name: &str, passphrase: &str) -> Result<(), String> {
+        let mut base_data = FileOps::parse(path).map_err(|e| e.to_string())?;
         let keypairs = match base_data["keypairs"].as_array_mut() {
             Some(data) => data,
             None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["keypairs.json".to_string()]));
-                panic!("Failed to read keypairs.json, has the data been modified or the file moved or deleted?");
+                return Err("Failed to read keypairs.json, has the data been modified or the file moved or deleted?".to_string());
             }
         };
-        for k in keypairs {
-            if k["name"] == name {
-                let mut id = String::from("");
-                id.push_str(key.to_owned().as_str());
-                id.push_str("_key");
-                // replace " in returned Value with empty string
-                return k[id].to_string().replace("\"", "");
+        let key_pair = match keypairs.iter_mut().find(|k| k["name"] == *name) {
+            Some(k) => k,
+            None => return Err(format!("No key pair found under {}", name)),
+        };
+        let private_key = match key_pair["private_key"].as_str() {
+            Some(key) => decode(key).map_err(|e| e.to_string())?,
+            None => return Err(format!("No unencrypted private key found under {}", name)),
+        };
+
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = KeyPair::cipher(passphrase, &salt)
+            .encrypt(&nonce, private_key.as_slice())
+            .map_err(|e| e.to_string())?;
+        let encrypted = EncryptedKey {
+            ciphertext: STANDARD.encode(ciphertext),
+            nonce: STANDARD.encode(nonce),
+            salt: STANDARD.encode(salt),
+        };
+
+        if let Some(entry) = key_pair.as_object_mut() {
+            entry.remove("private_key");
+        }
+        key_pair["encrypted_private_key"] = to_value(encrypted).map_err(|e| e.to_string())?;
+        FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())
+    }
+
+    /// Decrypts a private key previously encrypted by
+    /// save_encrypted and returns the full key pair
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path      -> path to keypairs.json
+    /// name: &str       -> name of the account for this key pair
+    /// passphrase: &str -> passphrase the key was encrypted with
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<KeyPair, String>
+    /// ```
+    pub fn load_decrypted(path: &Path, name: &str, passphrase: &str) -> Result<KeyPair, String> {
+        let base_data = FileOps::parse(path).map_err(|e| e.to_string())?;
+        let keypairs = match base_data["keypairs"].as_array() {
+            Some(data) => data,
+            None => {
+                return Err("Failed to read keypairs.json, has the data been modified or the file moved or deleted?".to_string());
             }
+        };
+        let key_pair = match keypairs.iter().find(|k| k["name"] == *name) {
+            Some(k) => k,
+            None => return Err(format!("No key pair found under {}", name)),
+        };
+        let encrypted: EncryptedKey = match from_value(key_pair["encrypted_private_key"].clone()) {
+            Ok(val) => val,
+            Err(_) => return Err(format!("No encrypted private key found under {}", name)),
+        };
+
+        let ciphertext = STANDARD
+            .decode(&encrypted.ciphertext)
+            .map_err(|e| e.to_string())?;
+        let nonce = STANDARD
+            .decode(&encrypted.nonce)
+            .map_err(|e| e.to_string())?;
+        let salt = STANDARD
+            .decode(&encrypted.salt)
+            .map_err(|e| e.to_string())?;
+        if nonce.len() != 12 {
+            return Err(format!("Invalid nonce stored for {}", name));
         }
-        format!("No key pair found under {name}").to_string()
+
+        let private_key = match KeyPair::cipher(passphrase, &salt)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        {
+            Ok(key) => key,
+            Err(_) => {
+                return Err(format!(
+                    "Failed to decrypt private key for {}, is the passphrase correct?",
+                    name
+                ))
+            }
+        };
+
+        Ok(KeyPair {
+            name: name.to_string(),
+            public_key: key_pair["public_key"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            private_key: encode(private_key),
+        })
+    }
+
+    /// Derives an AES-256-GCM cipher from a passphrase
+    /// and salt using PBKDF2-HMAC-SHA256
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// passphrase: &str -> passphrase to derive the key from
+    /// salt: &[u8]      -> random salt stored with the ciphertext
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Aes256Gcm
+    /// ```
+    fn cipher(passphrase: &str, salt: &[u8]) -> Aes256Gcm {
+        let mut key = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+        Aes256Gcm::new(&key.into())
     }
 
     /// Signs a transaction on the blockchain using
@@ -124,7 +432,7 @@ impl KeyPair {
     ///
     /// # Args
     /// ```
-    /// hash: String        -> transaction hash to sign
+    /// message: &[u8]      -> canonical transaction bytes to sign
     /// private_key: String -> private key to sign with
     /// ```
     ///
@@ -132,7 +440,7 @@ impl KeyPair {
     /// ```
     /// (String, String)
     /// ```
-    pub fn sign(hash: &String, private_key: String) -> (String, String) {
+    pub fn sign(message: &[u8], private_key: String) -> (String, String) {
         let key_bytes = match decode(&private_key) {
             Ok(key) => key,
             Err(e) => {
@@ -153,10 +461,112 @@ impl KeyPair {
                 );
             }
         };
-        let signature: Signature = signing_key.sign(&hash.as_bytes());
+        let signature: Signature = signing_key.sign(message);
         (encode(signature.to_bytes()), encode(signing_key.to_bytes()))
     }
 
+    /// Signs a transaction hash and also returns the
+    /// recovery id needed to recover the signers public
+    /// key from the signature
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// hash: &str          -> transaction hash to sign
+    /// private_key: String -> private key to sign with
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// (String, u8) -> hex encoded signature and recovery id
+    /// ```
+    pub fn sign_recoverable(hash: &str, private_key: String) -> (String, u8) {
+        let key_bytes = match decode(&private_key) {
+            Ok(key) => key,
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 3, Some(vec![private_key.clone()]));
+                panic!(
+                    "Failed to decode private key while signing transaction: {}",
+                    e
+                );
+            }
+        };
+        let signing_key = match SigningKey::from_slice(key_bytes.as_slice()) {
+            Ok(key) => key,
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 4, None);
+                panic!(
+                    "Failed to decode signing key from bytes while signing transaction: {}",
+                    e
+                );
+            }
+        };
+        let (signature, recovery_id) = match signing_key.sign_recoverable(hash.as_bytes()) {
+            Ok(val) => val,
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 4, None);
+                panic!("Failed to sign transaction: {}", e);
+            }
+        };
+        (encode(signature.to_bytes()), recovery_id.to_byte())
+    }
+
+    /// Recovers the hex encoded public key of the account
+    /// that produced a signature so it can be compared
+    /// against a wallet address. Each recovery id is tried
+    /// in turn and the first key recovered is returned, use
+    /// recover_public_keys to check every candidate
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// message: &[u8]         -> message that was signed
+    /// signature: &Signature  -> signature over the message
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<String>
+    /// ```
+    #[allow(dead_code)]
+    pub fn recover_public_key(message: &[u8], signature: &Signature) -> Option<String> {
+        KeyPair::recover_public_keys(message, signature)
+            .into_iter()
+            .next()
+    }
+
+    /// Recovers every hex encoded public key that could have
+    /// produced a signature by trying recovery ids 0 to 3.
+    /// Each candidate verifies the signature so the signer
+    /// has to be picked out by another check, e.g. which
+    /// candidate owns a wallet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// message: &[u8]         -> message that was signed
+    /// signature: &Signature  -> signature over the message
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<String>
+    /// ```
+    pub fn recover_public_keys(message: &[u8], signature: &Signature) -> Vec<String> {
+        (0..=3)
+            .filter_map(RecoveryId::from_byte)
+            .filter_map(|id| VerifyingKey::recover_from_msg(message, signature, id).ok())
+            .filter(|key| key.verify(message, signature).is_ok())
+            .map(|key| encode(key.to_encoded_point(false).as_bytes()))
+            .collect()
+    }
+
     /// Verifies a transaction on the blockchain using
     /// the account holders public key
     ///
@@ -165,21 +575,49 @@ impl KeyPair {
     ///
     /// # Args
     /// ```
-    /// signature: String -> signature to verify
-    /// hash: String      -> transaction hash to verify
+    /// signature: Signature     -> signature to verify
+    /// signing_key: SigningKey  -> key the signature was made with
+    /// message: &[u8]           -> canonical transaction bytes that were signed
     /// ```
     ///
     /// # Returns
     /// ```
     /// bool
     /// ```
-    pub fn verify(signature: Signature, signing_key: SigningKey, hash: String) -> bool {
+    pub fn verify(signature: Signature, signing_key: SigningKey, message: &[u8]) -> bool {
         let verifying_key = VerifyingKey::from(&signing_key);
-        let verified = match verifying_key.verify(&hash.as_bytes(), &signature) {
-            Ok(_res) => true,
-            Err(_) => false,
+        verifying_key.verify(message, &signature).is_ok()
+    }
+
+    /// Verifies a hex encoded signature against a hex
+    /// encoded public key such as a wallet address. Any
+    /// value that cannot be decoded fails verification
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// signature: &str  -> hex encoded signature to verify
+    /// public_key: &str -> hex encoded SEC1 public key of the signer
+    /// message: &[u8]   -> canonical transaction bytes that were signed
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn verify_with_public_key(signature: &str, public_key: &str, message: &[u8]) -> bool {
+        let signature = match decode(signature).map(|bytes| Signature::from_slice(&bytes)) {
+            Ok(Ok(sig)) => sig,
+            _ => return false,
         };
-        verified
+        let verifying_key =
+            match decode(public_key).map(|bytes| VerifyingKey::from_sec1_bytes(&bytes)) {
+                Ok(Ok(key)) => key,
+                _ => return false,
+            };
+        verifying_key.verify(message, &signature).is_ok()
     }
 
     /// Extract Signature and SigningKey objects from encoded
@@ -230,8 +668,8 @@ impl KeyPair {
     }
 }
 
-/// Creates a SHA256 hash of the components of
-/// a block. The delimiter aims to prevent an
+/// Hashes the header fields of a block with the
+/// given algorithm. The delimiter aims to prevent an
 /// attack where the string components of the
 /// hash are combined in a different segments
 /// e.g.
@@ -245,23 +683,33 @@ impl KeyPair {
 ///
 /// # Args
 /// ```
-/// nonce: &String        -> block nonce value  
-/// prev_hash: &String    -> hash of the previous block
-/// transactions: &String -> JSON serialized String of transactions
+/// algo: HashAlgo    -> hash function to use
+/// timestamp: &str   -> timestamp of block creation
+/// prev_hash: &str   -> hash of the previous block
+/// nonce: &str       -> block nonce value
+/// merkle_root: &str -> merkle root of the block transactions
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn hash_block(nonce: &String, prev_hash: &String, transactions: &String) -> String {
+pub fn hash_block_header_with(
+    algo: HashAlgo,
+    timestamp: &str,
+    prev_hash: &str,
+    nonce: &str,
+    merkle_root: &str,
+) -> String {
     let mut values: String = String::from("");
-    values.push_str(nonce.as_str());
+    values.push_str(timestamp);
     values.push_str(DELIMITER);
-    values.push_str(prev_hash.as_str());
+    values.push_str(prev_hash);
     values.push_str(DELIMITER);
-    values.push_str(transactions.as_str());
-    digest(values)
+    values.push_str(nonce);
+    values.push_str(DELIMITER);
+    values.push_str(merkle_root);
+    algo.digest(&values)
 }
 
 /// Creates a SHA256 hash of the components of
@@ -279,67 +727,92 @@ pub fn hash_block(nonce: &String, prev_hash: &String, transactions: &String) ->
 ///
 /// # Args
 /// ```
-/// from_address: &String -> the senders private key
-/// to_address: &String   -> the recipients public key
-/// amount: &String       -> amount being sent
+/// from_address: &str -> the senders private key
+/// to_address: &str   -> the recipients public key
+/// amount: &str       -> amount being sent
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn hash_transaction(from_address: &String, to_address: &String, amount: &String) -> String {
+pub fn hash_transaction(from_address: &str, to_address: &str, amount: &str) -> String {
     let mut values: String = String::from("");
-    values.push_str(from_address.as_str());
+    values.push_str(from_address);
     values.push_str(DELIMITER);
-    values.push_str(to_address.as_str());
+    values.push_str(to_address);
     values.push_str(DELIMITER);
-    values.push_str(amount.as_str());
+    values.push_str(amount);
     digest(values)
 }
 
-/// Creates a Merkle Root by hashing all the transactions
-/// that are going to be added to a block
+/// Derives a shorter checksummed address from a hex
+/// encoded public key. The key is hashed with SHA256
+/// then RIPEMD160, a 4 byte checksum taken from a
+/// double SHA256 of that hash is appended, and the
+/// result is base58 encoded
 ///
 /// # Visibility
 /// public
 ///
 /// # Args
 /// ```
-/// path: &Path -> path to read transactions from
+/// public_key: &str -> hex encoded public key
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn get_merkle_root(path: &Path) -> String {
-    let mut base_data = FileOps::parse(path);
-    let transactions = match base_data["transactions"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["transactions.json".to_string()]),
-            );
-            panic!("Failed to read transactions.json, has the data been modified or the file moved or deleted?");
-        }
+pub fn derive_address(public_key: &str) -> String {
+    let key_bytes = decode(public_key).unwrap_or_else(|_| public_key.as_bytes().to_vec());
+    let mut payload = Ripemd160::digest(Sha256::digest(key_bytes)).to_vec();
+    let checksum = address_checksum(&payload);
+    payload.extend_from_slice(&checksum);
+    bs58::encode(payload).into_string()
+}
+
+/// Checks an address produced by derive_address by
+/// recomputing its checksum
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// address: &str -> base58 encoded address
+/// ```
+///
+/// # Returns
+/// ```
+/// bool
+/// ```
+pub fn validate_address(address: &str) -> bool {
+    let bytes = match bs58::decode(address).into_vec() {
+        Ok(bytes) if bytes.len() == 24 => bytes,
+        _ => return false,
     };
-    if transactions.len() > 0 {
-        let mut hashes = Vec::new();
-        for t in transactions {
-            hashes.push(Sha256::hash(t["hash"].to_string().as_bytes()));
-        }
-        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&hashes);
-        if let Some(root) = merkle_tree.root_hex() {
-            root
-        } else {
-            String::from("None")
-        }
-    } else {
-        String::from("None")
-    }
+    let (payload, checksum) = bytes.split_at(20);
+    address_checksum(payload) == checksum
+}
+
+/// Gets the first 4 bytes of a double SHA256 hash
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// payload: &[u8] -> bytes to checksum
+/// ```
+///
+/// # Returns
+/// ```
+/// [u8; 4]
+/// ```
+fn address_checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    [hash[0], hash[1], hash[2], hash[3]]
 }
 
 // Testing
@@ -347,8 +820,10 @@ pub fn get_merkle_root(path: &Path) -> String {
 mod test_crypto {
     use super::*;
 
-    use crate::mods::transaction::Transaction;
-    use serde_json::to_string;
+    use crate::mods::{amount::Amount, transaction::Transaction};
+    use serde_json::json;
+    use std::env::temp_dir;
+    use std::fs;
 
     #[test]
     fn test_sign_extract_verify() {
@@ -358,51 +833,103 @@ mod test_crypto {
             String::from("4cae0e746defac95cba2dd5cdb440bb54d102713aeedcad19a483851c0a5ef21");
 
         // get a signature and signing key by signing the test hash
-        let (sig, key) = KeyPair::sign(&test_hash, test_private_key);
+        let (sig, key) = KeyPair::sign(test_hash.as_bytes(), test_private_key);
 
         // extract the Signature and SigningKey
         let (signature, signing_key) = KeyPair::extract(sig, key);
 
         // assert verification of hash signature
-        assert!(KeyPair::verify(signature, signing_key, test_hash));
+        assert!(KeyPair::verify(
+            signature,
+            signing_key,
+            test_hash.as_bytes()
+        ));
     }
 
     #[test]
-    fn test_hash_block() {
-        let transactions: [Transaction; 1] = [Transaction {
-            hash: String::from("2".repeat(64)),
-            from_address: String::from("2".repeat(130)),
-            to_address: String::from("3".repeat(130)),
-            amount: 10,
-            signature: String::from("4".repeat(128)),
-        }];
+    fn test_recover_public_key() {
+        let key_pair = KeyPair::generate(String::from("test"));
+        let test_hash = "0".repeat(64);
 
-        let transaction_string = match to_string(&transactions) {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "Error parsing transaction struct to serde_json Value::String: {}",
-                e
-            ),
-        };
+        let (sig, _) = KeyPair::sign_recoverable(&test_hash, key_pair.private_key);
+        let signature = Signature::from_slice(decode(sig).unwrap().as_slice()).unwrap();
+        let recovered = KeyPair::recover_public_key(test_hash.as_bytes(), &signature);
+        let candidates = KeyPair::recover_public_keys(test_hash.as_bytes(), &signature);
 
-        assert_eq!(
-            "c47b8a851113808578895f8f783961e38d9a0c481f1f921d90ac9c4905eca797",
-            hash_block(
-                &String::from("165"),
-                &String::from("1").repeat(64),
-                &transaction_string,
-            )
-        );
+        assert!(recovered.is_some());
+        assert_eq!(recovered.as_ref(), candidates.first());
+        assert!(candidates.contains(&key_pair.public_key));
+    }
+
+    #[test]
+    fn test_encrypted_private_key() {
+        let key_pair = KeyPair::generate(String::from("test"));
+        let path = temp_dir().join("mockchain_test_encrypted_key.json");
+        fs::write(&path, json!({ "keypairs": [&key_pair] }).to_string()).unwrap();
+
+        let saved = KeyPair::save_encrypted(&path, "test", "correct horse");
+        let contents = fs::read_to_string(&path).unwrap();
+        let loaded = KeyPair::load_decrypted(&path, "test", "correct horse");
+        let wrong = KeyPair::load_decrypted(&path, "test", "battery staple");
+        fs::remove_file(&path).unwrap();
+
+        assert!(saved.is_ok());
+        assert!(!contents.contains(&key_pair.private_key));
+        let loaded = loaded.unwrap();
+        assert_eq!(key_pair.private_key, loaded.private_key);
+        assert_eq!(key_pair.public_key, loaded.public_key);
+        assert!(wrong.is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let key_pair = KeyPair::generate(String::from("alice"));
+        let other = KeyPair::generate(String::from("bob"));
+        let swapped = json!({
+            "name": "bob",
+            "public_key": key_pair.public_key,
+            "private_key": other.private_key,
+        });
+        let path = temp_dir().join("mockchain_test_load_keypair.json");
+        fs::write(
+            &path,
+            json!({ "keypairs": [&key_pair, swapped] }).to_string(),
+        )
+        .unwrap();
+
+        let loaded = KeyPair::load(&path, "alice");
+        let mismatched = KeyPair::load(&path, "bob");
+        let missing = KeyPair::load(&path, "carol");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(key_pair.private_key, loaded.unwrap().private_key);
+        assert!(mismatched.unwrap_err().contains("is corrupt"));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let first = KeyPair::from_seed(String::from("first"), &[7u8; 32]);
+        let second = KeyPair::from_seed(String::from("second"), &[7u8; 32]);
+        let other = KeyPair::from_seed(String::from("other"), &[8u8; 32]);
+
+        assert_eq!(first.public_key, second.public_key);
+        assert_eq!(first.private_key, second.private_key);
+        assert_ne!(first.public_key, other.public_key);
     }
 
     #[test]
     fn test_hash_transactions() {
         let transactions: [Transaction; 1] = [Transaction {
-            hash: String::from("2".repeat(64)),
-            from_address: String::from("2".repeat(130)),
-            to_address: String::from("3".repeat(130)),
-            amount: 10,
-            signature: String::from("4".repeat(128)),
+            hash: "2".repeat(64),
+            txid: String::new(),
+            from_address: "2".repeat(130),
+            to_address: "3".repeat(130),
+            amount: Amount::new(10).unwrap(),
+            outputs: Vec::new(),
+            fee: 0,
+            timestamp: String::new(),
+            signature: "4".repeat(128),
         }];
 
         assert_eq!(
@@ -414,4 +941,29 @@ mod test_crypto {
             )
         );
     }
+
+    #[test]
+    fn test_derive_address() {
+        let key_pair = KeyPair::from_seed(String::from("test"), &[7u8; 32]);
+
+        let address = derive_address(&key_pair.public_key);
+
+        assert!(address.len() < key_pair.public_key.len());
+        assert_eq!(address, derive_address(&key_pair.public_key));
+        assert!(validate_address(&address));
+    }
+
+    #[test]
+    fn test_validate_address_typo() {
+        let key_pair = KeyPair::from_seed(String::from("test"), &[7u8; 32]);
+        let address = derive_address(&key_pair.public_key);
+
+        for i in 0..address.len() {
+            let mut chars: Vec<char> = address.chars().collect();
+            chars[i] = if chars[i] == '2' { '3' } else { '2' };
+            let typo: String = chars.into_iter().collect();
+            assert!(!validate_address(&typo));
+        }
+        assert!(!validate_address("0OIl"));
+    }
 }
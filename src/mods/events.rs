@@ -0,0 +1,234 @@
+/*
+    In-process notifications for ledger changes so
+    callers can react without polling the data files
+*/
+
+// std library
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+// listeners registered with LedgerEvents, keyed by the id handed back
+type Listener = Arc<dyn Fn(&Event) + Send + Sync>;
+static LISTENERS: Mutex<Vec<(usize, Listener)>> = Mutex::new(Vec::new());
+static NEXT_ID: Mutex<usize> = Mutex::new(0);
+
+/// Defines a change made to the data files
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// BalanceUpdated      -> a wallet balance in wallets.json changed
+/// BlockAdded          -> a block was appended to blockchain.json
+/// TransactionAccepted -> a transaction was added to the mempool
+/// ```
+///
+/// # Derives
+/// ```
+/// Clone, Debug, PartialEq
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    BalanceUpdated {
+        path: PathBuf,
+        address: String,
+        balance: i64,
+    },
+    BlockAdded {
+        path: PathBuf,
+        index: usize,
+        hash: String,
+    },
+    TransactionAccepted {
+        path: PathBuf,
+        txid: String,
+    },
+}
+
+/// Registry of callbacks run synchronously, in the
+/// order they were registered, each time an Event is
+/// emitted. Events are only emitted once the change has
+/// been written and its file lock released
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// None
+pub struct LedgerEvents {}
+
+impl LedgerEvents {
+    /// Registers a callback to run for every event
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// listener: F -> callback taking the emitted &Event
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize -> id to pass to unregister
+    /// ```
+    pub fn register<F>(listener: F) -> usize
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        let mut next_id = NEXT_ID.lock().unwrap_or_else(|e| e.into_inner());
+        let id = *next_id;
+        *next_id += 1;
+        LISTENERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, Arc::new(listener)));
+        id
+    }
+
+    /// Removes a callback so it no longer sees events
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// id: usize -> id returned by register
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool -> whether a callback was removed
+    /// ```
+    pub fn unregister(id: usize) -> bool {
+        let mut listeners = LISTENERS.lock().unwrap_or_else(|e| e.into_inner());
+        let count = listeners.len();
+        listeners.retain(|(listener_id, _)| *listener_id != id);
+        listeners.len() != count
+    }
+
+    /// Runs every registered callback with an event. The
+    /// registry is not held while they run so a callback
+    /// may register or unregister others
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// event: Event -> change that was made
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn emit(event: Event) {
+        let listeners: Vec<Listener> = LISTENERS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(_, listener)| Arc::clone(listener))
+            .collect();
+        for listener in listeners {
+            listener(&event);
+        }
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_events {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::mods::{
+        base::Blockchain,
+        block::{Block, DIFFICULTY},
+        config::Config,
+        crypto::KeyPair,
+        file::FileOps,
+        mempool,
+        transaction::Transaction,
+        wallet::Wallet,
+    };
+
+    #[test]
+    fn test_listener() {
+        let config = Config::new(&temp_dir().join("mockchain_test_events"));
+        FileOps::init(&config, false);
+        let data_dir = config.data_dir.clone();
+        let (wallets_path, blockchain_path) = (&config.wallets_path, &config.blockchain_path);
+        let alice = KeyPair::from_seed(String::from("alice"), &[1u8; 32]);
+        let a = alice.public_key.clone();
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": a, "balance": 10 },
+            { "name": "bob", "address": "b", "balance": 0 },
+        ]});
+        fs::write(wallets_path, wallets.to_string()).unwrap();
+        let mut tx = json!({ "from_address": a, "to_address": "b", "amount": 2 });
+        tx["hash"] = json!(Transaction::content_hash(&tx));
+        let (signature, _) = KeyPair::sign(&Transaction::canonical_bytes(&tx), alice.private_key);
+        tx["signature"] = json!(signature);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let dir = data_dir.clone();
+        // other tests emit events too, so only keep the ones for this data directory
+        let id = LedgerEvents::register(move |event| {
+            let path = match event {
+                Event::BalanceUpdated { path, .. }
+                | Event::BlockAdded { path, .. }
+                | Event::TransactionAccepted { path, .. } => path,
+            };
+            if path.starts_with(&dir) {
+                recorded.lock().unwrap().push(event.clone());
+            }
+        });
+        Wallet::update_balance(wallets_path, a.clone(), 5, "add").unwrap();
+        Wallet::update_balance(wallets_path, a.clone(), 3, "subtract").unwrap();
+        let rejected = Wallet::update_balance(wallets_path, a.clone(), 100, "subtract");
+        Blockchain::add_block(
+            blockchain_path,
+            Block::new(String::new(), vec![]),
+            DIFFICULTY,
+        )
+        .unwrap();
+        let mined = Blockchain::get_block_by_index(blockchain_path, 1).unwrap();
+        mempool::add(&config, tx.clone()).unwrap();
+        let removed = LedgerEvents::unregister(id);
+        Wallet::update_balance(wallets_path, a.clone(), 1, "add").unwrap();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(rejected.is_err());
+        assert!(removed);
+        assert!(!LedgerEvents::unregister(id));
+        assert_eq!(
+            vec![
+                Event::BalanceUpdated {
+                    path: wallets_path.clone(),
+                    address: a.clone(),
+                    balance: 15,
+                },
+                Event::BalanceUpdated {
+                    path: wallets_path.clone(),
+                    address: a.clone(),
+                    balance: 12,
+                },
+                Event::BlockAdded {
+                    path: blockchain_path.clone(),
+                    index: 1,
+                    hash: mined.hash,
+                },
+                Event::TransactionAccepted {
+                    path: config.transactions_path.clone(),
+                    txid: Transaction::txid(&tx),
+                },
+            ],
+            *seen.lock().unwrap()
+        );
+    }
+}
@@ -1,19 +1,111 @@
 // std library
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 // 3rd party crates
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use fs2::FileExt;
 use serde::Serialize;
-use serde_json::{from_str, to_string, to_value, Value};
+use serde_json::{from_str, to_string, to_string_pretty, to_value, Value};
 
 // imports
 use super::{
     base::{Blockchain, KeyPairs, SigningData, Transactions, Wallets},
+    block::Block,
+    config::Config,
     log::{Log, LogLevel},
+    storage::Storage,
+    wallet::Wallet,
 };
-use crate::{
-    BLOCKCHAIN_PATH, DATA_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH, WALLETS_PATH,
-};
+
+thread_local! {
+    // lock files held by this thread so a nested with_lock on the same path runs under the held lock
+    static HELD_LOCKS: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+/// Errors raised while reading data files
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// NotFound -> the file does not exist yet e.g. before init has run
+/// Io       -> the file could not be read
+/// Parse    -> the file content is not valid JSON
+/// Schema   -> the JSON does not have the expected structure
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug
+/// ```
+#[derive(Debug)]
+pub enum FileOpsError {
+    NotFound(PathBuf),
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Schema(String),
+}
+
+impl fmt::Display for FileOpsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileOpsError::NotFound(path) => write!(
+                f,
+                "No data file found at {}, has the data directory been initialised?",
+                path.display()
+            ),
+            FileOpsError::Io(e) => write!(f, "Error reading data file: {}", e),
+            FileOpsError::Parse(e) => write!(f, "Poorly formatted JSON found: {}", e),
+            FileOpsError::Schema(e) => write!(f, "Unexpected data file structure: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FileOpsError {}
+
+impl From<io::Error> for FileOpsError {
+    fn from(e: io::Error) -> Self {
+        FileOpsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for FileOpsError {
+    fn from(e: serde_json::Error) -> Self {
+        FileOpsError::Parse(e)
+    }
+}
+
+/// An advisory lock taken by with_lock, released when
+/// dropped so the lock is also freed if the closure
+/// panics
+///
+/// # Visibility
+/// private
+///
+/// # Fields
+/// ```
+/// path: PathBuf  -> path to the `.lock` file
+/// file: fs::File -> open handle holding the lock
+/// ```
+struct HeldLock {
+    path: PathBuf,
+    file: fs::File,
+}
+
+impl Drop for HeldLock {
+    fn drop(&mut self) {
+        HELD_LOCKS.with(|held| held.borrow_mut().remove(&self.path));
+        let _ = FileExt::unlock(&self.file);
+    }
+}
 
 /// File operations for working with JSON
 ///
@@ -33,59 +125,65 @@ pub struct FileOps {}
 impl FileOps {
     /// initialises the data files when a user first
     /// runs the app or decides to re-initialise the
-    /// blockchain
+    /// blockchain, and appends the genesis block to the
+    /// new blockchain
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
+    /// config: &Config         -> locations of the data files
     /// preserve_accounts: bool -> option to preserve wallet and key data
     /// ```
     ///
     /// # Returns
     /// Nothing
-    pub fn init(preserve_accounts: bool) {
-        if !Path::new(DATA_PATH.as_path()).exists() {
-            match fs::create_dir_all(DATA_PATH.as_path()) {
+    pub fn init(config: &Config, preserve_accounts: bool) {
+        let data_dir = config.data_dir.as_path();
+        if !data_dir.exists() {
+            match fs::create_dir_all(data_dir) {
                 Ok(_) => {}
                 Err(e) => {
                     Log::new_panic(LogLevel::ERROR, 7, None);
-                    panic!(
-                        "Error creating /.mockchain/data/ directory under $HOME: {}",
-                        e
-                    );
+                    panic!("Error creating {:?} directory: {}", data_dir, e);
                 }
             };
         }
 
         FileOps::init_helper(
             &Blockchain { blockchain: [] },
-            BLOCKCHAIN_PATH.as_path(),
+            config.blockchain_path.as_path(),
             "blockchain",
         );
 
         FileOps::init_helper(
             &Transactions { transactions: [] },
-            TRANSACTIONS_PATH.as_path(),
+            config.transactions_path.as_path(),
             "transactions",
         );
 
         FileOps::init_helper(
             &SigningData { signing_data: [] },
-            SIGNING_DATA_PATH.as_path(),
+            config.signing_data_path.as_path(),
             "signing",
         );
 
         if !preserve_accounts {
             FileOps::init_helper(
                 &KeyPairs { keypairs: [] },
-                KEYPAIRS_PATH.as_path(),
+                config.keypairs_path.as_path(),
                 "keypairs",
             );
 
-            FileOps::init_helper(&Wallets { wallets: [] }, &WALLETS_PATH.as_path(), "wallets");
+            FileOps::init_helper(
+                &Wallets { wallets: [] },
+                config.wallets_path.as_path(),
+                "wallets",
+            );
         }
+
+        Block::add_genesis_block(config.blockchain_path.as_path());
     }
 
     /// Init helper
@@ -110,7 +208,7 @@ impl FileOps {
                 panic!("Error initialising data file: {}.json: {}", &file_name, e);
             }
         };
-        match fs::write(data_file, data) {
+        match FileOps::atomic_write(data_file, &data) {
             Ok(_) => {}
             Err(e) => {
                 Log::new_panic(LogLevel::ERROR, 9, Some(vec![file_name.to_string()]));
@@ -120,7 +218,7 @@ impl FileOps {
     }
 
     /// Writes the current state of the blockchain to
-    /// file in blockchain.json
+    /// file in blockchain.json as compact JSON
     ///
     /// # Visibility
     /// public
@@ -136,6 +234,28 @@ impl FileOps {
     /// Nothing
     ///
     pub fn write<T: Serialize>(path: &Path, base: &str, obj: T) {
+        FileOps::write_with(path, base, obj, false);
+    }
+
+    /// Appends an object to a data file, choosing
+    /// pretty-printed JSON for reading by hand or
+    /// compact JSON for smaller, faster writes
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path -> &Path path slice
+    /// base -> &str slice of base struct name
+    /// obj  -> an object T that implements Serialize
+    /// pretty -> bool, true to pretty-print the file
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    ///
+    pub fn write_with<T: Serialize>(path: &Path, base: &str, obj: T, pretty: bool) {
         // convert the obj into a serde_json::Value
         let value = match to_value(&obj) {
             Ok(val) => val,
@@ -145,7 +265,13 @@ impl FileOps {
             }
         };
         // parse data from base file
-        let mut base_data = FileOps::parse(path);
+        let mut base_data = match FileOps::parse(path) {
+            Ok(data) => data,
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 12, Some(vec![format!("{:?}", path)]));
+                panic!("Error parsing data file content at {:?}: {}", path, e);
+            }
+        };
         let data = match base_data[base].as_array_mut() {
             Some(d) => d,
             None => {
@@ -154,8 +280,19 @@ impl FileOps {
             }
         };
         data.push(value);
+        let contents = match pretty {
+            true => to_string_pretty(&base_data),
+            false => to_string(&base_data),
+        };
+        let contents = match contents {
+            Ok(val) => val,
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 10, Some(vec![base.to_string()]));
+                panic!("Failed to serialize {}.json: {}", base, e);
+            }
+        };
         // write data back to file (full overwrite with new data appended)
-        match fs::write(path, base_data.to_string()) {
+        match FileOps::atomic_write(path, &contents) {
             Ok(_) => {}
             Err(e) => {
                 Log::new_panic(LogLevel::ERROR, 9, Some(vec![base.to_string()]));
@@ -165,38 +302,44 @@ impl FileOps {
     }
 
     /// Write a new value to the balance field of an account
+    /// in already parsed wallets.json data and write the
+    /// data back to file
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// address: String -> name of account to lookup
-    /// balance: i32    -> new balance to write
+    /// path: &Path      -> path to wallets.json
+    /// base_data: Value -> parsed wallets.json
+    /// address: String  -> name of account to lookup
+    /// balance: i64     -> new balance to write
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn write_balance(address: String, balance: i32) {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
+    /// ```
+    /// Result<(), FileOpsError> -> Schema if the "wallets" key is missing
+    /// ```
+    pub fn write_balance(
+        path: &Path,
+        base_data: Value,
+        address: String,
+        balance: i64,
+    ) -> Result<(), FileOpsError> {
+        let mut base_data = FileOps::validate(base_data, "wallets")?;
         let wallets = match base_data["wallets"].as_array_mut() {
             Some(data) => data,
             None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
+                return Err(FileOpsError::Schema(
+                    "'wallets' is not an array".to_string(),
+                ))
             }
         };
         for wallet in wallets {
-            if wallet["address"].to_string() == address {
+            if wallet["address"] == address.trim_matches('"') {
                 if let Ok(value) = to_value(balance) {
                     wallet["balance"] = value;
-                    match fs::write(WALLETS_PATH.as_path(), base_data.to_string()) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            Log::new_panic(LogLevel::ERROR, 9, Some(vec!["wallets".to_string()]));
-                            panic!("Failed to write wallets.json: {}", e);
-                        }
-                    };
+                    FileOps::atomic_write(path, base_data.to_string())?;
                     break;
                 } else {
                     Log::new_panic(LogLevel::ERROR, 11, Some(vec![balance.to_string()]));
@@ -207,9 +350,10 @@ impl FileOps {
                 };
             }
         }
+        Ok(())
     }
 
-    /// Parse a JSON string into a serde_json Value Object
+    /// Overwrites a data file with the given JSON data
     ///
     /// # Visibility
     /// public
@@ -217,31 +361,753 @@ impl FileOps {
     /// # Args
     /// ```
     /// path -> &Path path slice
+    /// data -> &Value data to write
     /// ```
     ///
     /// # Returns
     /// ```
-    /// Value
+    /// Result<(), FileOpsError>
+    /// ```
+    pub fn overwrite(path: &Path, data: &Value) -> Result<(), FileOpsError> {
+        FileOps::atomic_write(path, data.to_string())?;
+        Ok(())
+    }
+
+    /// Runs a read-modify-write closure while holding an
+    /// exclusive advisory lock on a data file so that
+    /// concurrent updates cannot overwrite each other. The
+    /// lock is taken on a `.lock` file next to the data
+    /// file as atomic writes replace the data file itself.
+    /// A closure that calls with_lock on the same path again
+    /// runs the nested closure under the lock already held
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> data file path
+    /// f: F        -> closure to run while the lock is held
     /// ```
-    pub fn parse(path: &Path) -> Value {
-        let json_str = match fs::read_to_string(Path::new(path)) {
-            Ok(content) => content,
+    ///
+    /// # Returns
+    /// ```
+    /// R -> the closures return value
+    /// ```
+    pub fn with_lock<F, R>(path: &Path, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".lock");
+        let lock_path = path.with_file_name(file_name);
+        if HELD_LOCKS.with(|held| held.borrow().contains(&lock_path)) {
+            return f();
+        }
+        let lock_file = match fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .and_then(|file| file.lock_exclusive().map(|_| file))
+        {
+            Ok(file) => file,
             Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 12, Some(vec![format!("{:?}", path)]));
-                panic!(
-                    "Error parsing data file content at {}: {}",
-                    format!("{:?}", path),
-                    e
-                );
+                Log::new_panic(LogLevel::ERROR, 18, Some(vec![format!("{:?}", path)]));
+                panic!("Failed to acquire lock on {:?}: {}", path, e);
             }
         };
-        let value = match from_str(&json_str) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 13, Some(vec![json_str.clone()]));
-                panic!("Poorly formatted JSON found: {}", e);
+        HELD_LOCKS.with(|held| held.borrow_mut().insert(lock_path.clone()));
+        let _held = HeldLock {
+            path: lock_path,
+            file: lock_file,
+        };
+        f()
+    }
+
+    /// Copies every data file into a named snapshot under
+    /// `<data dir>/snapshots`, replacing an older snapshot
+    /// with the same name
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// config: &Config -> locations of the data files
+    /// name: &str      -> name of the snapshot
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn snapshot(config: &Config, name: &str) -> Result<(), String> {
+        let snapshot_dir = FileOps::snapshot_dir(config, name)?;
+        fs::create_dir_all(&snapshot_dir).map_err(|e| e.to_string())?;
+        for path in FileOps::data_files(config) {
+            let contents = FileOps::with_lock(path, || fs::read_to_string(path))
+                .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+            let target = snapshot_dir.join(path.file_name().unwrap_or_default());
+            FileOps::atomic_write(&target, &contents).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Copies the data files of a named snapshot back over
+    /// the current data files. wallets_index.json is not
+    /// part of a snapshot so it is rebuilt from the restored
+    /// wallets
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// config: &Config -> locations of the data files
+    /// name: &str      -> name of the snapshot
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn restore(config: &Config, name: &str) -> Result<(), String> {
+        let snapshot_dir = FileOps::snapshot_dir(config, name)?;
+        if !snapshot_dir.is_dir() {
+            return Err(format!("No snapshot found under name '{}'", name));
+        }
+        // read everything first so a broken snapshot leaves the data untouched
+        let mut restored = Vec::new();
+        for path in FileOps::data_files(config) {
+            let source = snapshot_dir.join(path.file_name().unwrap_or_default());
+            let contents = fs::read_to_string(&source)
+                .map_err(|e| format!("Snapshot '{}' is incomplete: {}", name, e))?;
+            restored.push((path, contents));
+        }
+        for (path, contents) in restored {
+            FileOps::with_lock(path, || FileOps::atomic_write(path, &contents))
+                .map_err(|e| e.to_string())?;
+        }
+        FileOps::with_lock(&config.wallets_path, || {
+            Wallet::refresh_index(&FileOps {}, &config.wallets_path)
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    /// Gets the directory a named snapshot is kept in
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// config: &Config -> locations of the data files
+    /// name: &str      -> name of the snapshot
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<PathBuf, String> -> error if the name is not a plain file name
+    /// ```
+    fn snapshot_dir(config: &Config, name: &str) -> Result<PathBuf, String> {
+        let plain = Path::new(name).file_name().is_some_and(|n| n == name);
+        if !plain {
+            return Err(format!("Invalid snapshot name '{}'", name));
+        }
+        Ok(config.data_dir.join("snapshots").join(name))
+    }
+
+    /// Lists the data files held in a data directory
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// config: &Config -> locations of the data files
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// [&Path; 5]
+    /// ```
+    fn data_files(config: &Config) -> [&Path; 5] {
+        [
+            &config.blockchain_path,
+            &config.transactions_path,
+            &config.wallets_path,
+            &config.keypairs_path,
+            &config.signing_data_path,
+        ]
+    }
+
+    /// Writes the full content of a data file to a temp
+    /// file in the same directory then renames it over
+    /// the target. The rename is atomic on the same file
+    /// system so readers only ever see the old or the new
+    /// complete file. The temp file is removed if either
+    /// step fails
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path  -> data file path
+    /// contents: C  -> full file content to write
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// io::Result<()>
+    /// ```
+    fn atomic_write<C: AsRef<[u8]>>(path: &Path, contents: C) -> io::Result<()> {
+        let tmp_path = FileOps::tmp_path(path);
+        let result = fs::write(&tmp_path, contents).and_then(|_| fs::rename(&tmp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// Gets the temp file path used while writing a data file
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> data file path
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    fn tmp_path(path: &Path) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        path.with_file_name(file_name)
+    }
+
+    /// Parse a JSON string into a serde_json Value Object
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path -> &Path path slice
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, FileOpsError>
+    /// ```
+    pub fn parse(path: &Path) -> Result<Value, FileOpsError> {
+        let json_str = fs::read_to_string(path).map_err(|e| FileOps::read_error(path, e))?;
+        let value = from_str(&json_str)?;
+        Ok(value)
+    }
+
+    /// Converts an error opening a data file, so a file
+    /// that does not exist yet is reported as NotFound
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path    -> path that was read
+    /// e: io::Error   -> error raised by the read
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// FileOpsError
+    /// ```
+    fn read_error(path: &Path, e: io::Error) -> FileOpsError {
+        match e.kind() {
+            io::ErrorKind::NotFound => FileOpsError::NotFound(path.to_path_buf()),
+            _ => FileOpsError::Io(e),
+        }
+    }
+
+    /// Reads a data file, retrying when a concurrent writer
+    /// causes an Interrupted or WouldBlock error. The delay
+    /// doubles after each failed attempt, starting from
+    /// `backoff_ms`. Any other error is returned at once
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding the file
+    /// path: &Path           -> path to the data file
+    /// attempts: u32         -> maximum number of reads, at least 1
+    /// backoff_ms: u64       -> delay before the first retry
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, FileOpsError>
+    /// ```
+    pub fn parse_retry(
+        storage: &dyn Storage,
+        path: &Path,
+        attempts: u32,
+        backoff_ms: u64,
+    ) -> Result<Value, FileOpsError> {
+        let mut delay = backoff_ms;
+        let mut attempt = 1;
+        loop {
+            match storage.read(path) {
+                Err(FileOpsError::Io(e)) if attempt < attempts && FileOps::is_transient(&e) => {
+                    log::warn!(
+                        "Reading {:?} failed on attempt {} of {}: {}",
+                        path,
+                        attempt,
+                        attempts,
+                        e
+                    );
+                    thread::sleep(Duration::from_millis(delay));
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Checks whether an I/O error is worth retrying
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// e: &io::Error -> error raised by a read
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    fn is_transient(e: &io::Error) -> bool {
+        matches!(
+            e.kind(),
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+        )
+    }
+
+    /// Writes a value as compact JSON, gzip compressed when
+    /// the path ends in .gz e.g. an archived blockchain.json.gz
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path   -> path to write, compressed if it ends in .gz
+    /// value: &Value -> data to write
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), FileOpsError>
+    /// ```
+    pub fn write_compressed(path: &Path, value: &Value) -> Result<(), FileOpsError> {
+        let json = to_string(value)?;
+        if !FileOps::is_compressed(path) {
+            return Ok(FileOps::atomic_write(path, json)?);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes())?;
+        Ok(FileOps::atomic_write(path, encoder.finish()?)?)
+    }
+
+    /// Parses a data file written by write_compressed,
+    /// decompressing it first when the path ends in .gz
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to read, compressed if it ends in .gz
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, FileOpsError>
+    /// ```
+    pub fn parse_compressed(path: &Path) -> Result<Value, FileOpsError> {
+        if !FileOps::is_compressed(path) {
+            return FileOps::parse(path);
+        }
+        let mut json_str = String::new();
+        let file = fs::File::open(path).map_err(|e| FileOps::read_error(path, e))?;
+        GzDecoder::new(file).read_to_string(&mut json_str)?;
+        Ok(from_str(&json_str)?)
+    }
+
+    /// Checks whether a path names a gzip file
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to check
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    fn is_compressed(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "gz")
+    }
+
+    /// Parse a data file and confirm its top-level key
+    /// holds an array before returning it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path     -> path to the data file
+    /// root_key: &str  -> expected top-level key e.g. "wallets"
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, String>
+    /// ```
+    pub fn parse_validated(path: &Path, root_key: &str) -> Result<Value, String> {
+        FileOps::parse(path)
+            .and_then(|data| FileOps::validate(data, root_key))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Confirms a parsed data file has an array under
+    /// its top-level key
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data: Value    -> parsed file content
+    /// root_key: &str -> expected top-level key e.g. "wallets"
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, FileOpsError>
+    /// ```
+    pub fn validate(data: Value, root_key: &str) -> Result<Value, FileOpsError> {
+        match data.get(root_key) {
+            Some(Value::Array(_)) => Ok(data),
+            Some(_) => Err(FileOpsError::Schema(format!(
+                "'{}' is not an array",
+                root_key
+            ))),
+            None => Err(FileOpsError::Schema(format!(
+                "missing top-level '{}' key",
+                root_key
+            ))),
+        }
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_file {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::env::temp_dir;
+
+    use crate::mods::storage::MemStorage;
+
+    /// fails the first `failures` reads with `kind`
+    struct FlakyStorage {
+        inner: MemStorage,
+        failures: u32,
+        kind: io::ErrorKind,
+        reads: Cell<u32>,
+    }
+
+    impl Storage for FlakyStorage {
+        fn read(&self, key: &Path) -> Result<Value, FileOpsError> {
+            self.reads.set(self.reads.get() + 1);
+            if self.reads.get() <= self.failures {
+                return Err(FileOpsError::Io(io::Error::from(self.kind)));
             }
+            self.inner.read(key)
+        }
+
+        fn write(&self, key: &Path, value: &Value) -> Result<(), FileOpsError> {
+            self.inner.write(key, value)
+        }
+    }
+
+    fn flaky(failures: u32, kind: io::ErrorKind) -> FlakyStorage {
+        let inner = MemStorage::new();
+        inner
+            .write(
+                Path::new("wallets.json"),
+                &serde_json::json!({ "wallets": [] }),
+            )
+            .unwrap();
+        FlakyStorage {
+            inner,
+            failures,
+            kind,
+            reads: Cell::new(0),
+        }
+    }
+
+    #[test]
+    fn test_init_genesis() {
+        let config = Config::new(&temp_dir().join("mockchain_test_init_genesis"));
+        FileOps::init(&config, false);
+        let path = config.blockchain_path.clone();
+        // a second genesis block is never appended
+        Block::add_genesis_block(&path);
+
+        let data = FileOps::parse(&path).unwrap();
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        let blockchain = data["blockchain"].as_array().unwrap();
+        assert_eq!(1, blockchain.len());
+        assert_eq!("0".repeat(64), blockchain[0]["previous_hash"]);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let config = Config::new(&temp_dir().join("mockchain_test_snapshot"));
+        FileOps::init(&config, false);
+        let address = "0".repeat(130);
+        let wallets = serde_json::json!({ "wallets": [
+            { "name": "alice", "address": address, "balance": 50 },
+        ]});
+        fs::write(&config.wallets_path, wallets.to_string()).unwrap();
+
+        FileOps::snapshot(&config, "before").unwrap();
+        FileOps::write_balance(
+            &config.wallets_path,
+            FileOps::parse(&config.wallets_path).unwrap(),
+            address.clone(),
+            5,
+        )
+        .unwrap();
+        let changed = FileOps::parse(&config.wallets_path).unwrap();
+        // an index built after the snapshot must not outlive the restore
+        let index_path = config.wallets_path.with_file_name("wallets_index.json");
+        let stale = serde_json::json!({ "index": { "1".repeat(130): 0 } });
+        fs::write(&index_path, stale.to_string()).unwrap();
+        let restored = FileOps::restore(&config, "before");
+        let reverted = FileOps::parse(&config.wallets_path).unwrap();
+        let index = FileOps::parse(&index_path).unwrap();
+        let missing = FileOps::restore(&config, "never_taken");
+        let escaped = FileOps::snapshot(&config, "../outside");
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(5, changed["wallets"][0]["balance"]);
+        assert_eq!(Ok(()), restored);
+        assert_eq!(50, reverted["wallets"][0]["balance"]);
+        assert_eq!(serde_json::json!({ "index": { address: 0 } }), index);
+        assert_eq!(
+            Err("No snapshot found under name 'never_taken'".to_string()),
+            missing
+        );
+        assert!(escaped.is_err());
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let blocks: Vec<Block> = (0..50).map(|_| Block::genesis()).collect();
+        let value = serde_json::json!({ "blockchain": blocks });
+        let compressed = temp_dir().join("mockchain_test_compressed.json.gz");
+        let plain = temp_dir().join("mockchain_test_compressed.json");
+
+        FileOps::write_compressed(&compressed, &value).unwrap();
+        FileOps::write_compressed(&plain, &value).unwrap();
+        let from_compressed = FileOps::parse_compressed(&compressed).unwrap();
+        let from_plain = FileOps::parse_compressed(&plain).unwrap();
+        let compressed_len = fs::metadata(&compressed).unwrap().len();
+        let plain_len = fs::metadata(&plain).unwrap().len();
+        fs::remove_file(&compressed).unwrap();
+        fs::remove_file(&plain).unwrap();
+
+        assert_eq!(value, from_compressed);
+        assert_eq!(
+            to_string(&value).unwrap(),
+            to_string(&from_compressed).unwrap()
+        );
+        assert_eq!(value, from_plain);
+        assert!(compressed_len < plain_len);
+    }
+
+    #[test]
+    fn test_parse_retry() {
+        let path = Path::new("wallets.json");
+        let busy = flaky(2, io::ErrorKind::Interrupted);
+        let recovered = FileOps::parse_retry(&busy, path, 3, 1);
+        let blocked = flaky(3, io::ErrorKind::WouldBlock);
+        let exhausted = FileOps::parse_retry(&blocked, path, 3, 1);
+        let denied = flaky(1, io::ErrorKind::PermissionDenied);
+        let not_retried = FileOps::parse_retry(&denied, path, 3, 1);
+
+        assert_eq!(serde_json::json!({ "wallets": [] }), recovered.unwrap());
+        assert_eq!(3, busy.reads.get());
+        assert!(matches!(exhausted, Err(FileOpsError::Io(_))));
+        assert_eq!(3, blocked.reads.get());
+        assert!(matches!(not_retried, Err(FileOpsError::Io(_))));
+        assert_eq!(1, denied.reads.get());
+    }
+
+    #[test]
+    fn test_parse_missing_file() {
+        let path = temp_dir().join("mockchain_test_parse_missing.json");
+        let compressed = temp_dir().join("mockchain_test_parse_missing.json.gz");
+
+        let result = FileOps::parse(&path);
+        let validated = FileOps::parse_validated(&path, "wallets");
+        let unzipped = FileOps::parse_compressed(&compressed);
+
+        assert!(matches!(result, Err(FileOpsError::NotFound(p)) if p == path));
+        assert!(validated.unwrap_err().contains("No data file found"));
+        assert!(matches!(unzipped, Err(FileOpsError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        let path = temp_dir().join("mockchain_test_parse_invalid.json");
+        fs::write(&path, "{\"wallets\": [").unwrap();
+
+        let result = FileOps::parse(path.as_path());
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(FileOpsError::Parse(_))));
+    }
+
+    #[test]
+    fn test_parse_validated_missing_key() {
+        let path = temp_dir().join("mockchain_test_parse_missing_key.json");
+        fs::write(&path, "{\"keypairs\": []}").unwrap();
+
+        let result = FileOps::parse_validated(&path, "wallets");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Err("Unexpected data file structure: missing top-level 'wallets' key".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn test_parse_validated_not_array() {
+        let path = temp_dir().join("mockchain_test_parse_not_array.json");
+        fs::write(&path, "{\"wallets\": {\"name\": \"alice\"}}").unwrap();
+
+        let result = FileOps::parse_validated(&path, "wallets");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Err("Unexpected data file structure: 'wallets' is not an array".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn test_write_with() {
+        let pretty_path = temp_dir().join("mockchain_test_write_pretty.json");
+        let compact_path = temp_dir().join("mockchain_test_write_compact.json");
+        fs::write(&pretty_path, "{\"wallets\": []}").unwrap();
+        fs::write(&compact_path, "{\"wallets\": []}").unwrap();
+        let wallet = serde_json::json!({ "name": "alice", "address": "a", "balance": 0 });
+
+        FileOps::write_with(&pretty_path, "wallets", &wallet, true);
+        FileOps::write_with(&compact_path, "wallets", &wallet, false);
+        let pretty = fs::read_to_string(&pretty_path).unwrap();
+        let compact = fs::read_to_string(&compact_path).unwrap();
+        fs::remove_file(&pretty_path).unwrap();
+        fs::remove_file(&compact_path).unwrap();
+
+        assert!(pretty.contains('\n'));
+        assert!(!compact.contains('\n'));
+        assert_eq!(
+            from_str::<Value>(&pretty).unwrap(),
+            from_str::<Value>(&compact).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_atomic_write() {
+        let path = temp_dir().join("mockchain_test_atomic_write.json");
+        fs::write(&path, "{\"wallets\": []}").unwrap();
+        let wallet = serde_json::json!({ "name": "alice", "address": "a", "balance": 0 });
+
+        FileOps::write(&path, "wallets", wallet);
+        FileOps::write_balance(&path, FileOps::parse(&path).unwrap(), String::from("a"), 10)
+            .unwrap();
+        let data = FileOps::parse(&path);
+        let tmp_exists = FileOps::tmp_path(&path).exists();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(10, data.unwrap()["wallets"][0]["balance"]);
+        assert!(!tmp_exists);
+    }
+
+    #[test]
+    fn test_with_lock_nested() {
+        let path = temp_dir().join("mockchain_test_with_lock_nested.json");
+
+        let nested = FileOps::with_lock(&path, || FileOps::with_lock(&path, || 1) + 1);
+        // the lock is released so another thread can take it
+        let other = {
+            let path = path.clone();
+            thread::spawn(move || FileOps::with_lock(&path, || 3))
+                .join()
+                .unwrap()
         };
-        value
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(2, nested);
+        assert_eq!(3, other);
+    }
+
+    #[test]
+    fn test_write_balance_schema() {
+        let path = temp_dir().join("mockchain_test_write_balance_schema.json");
+        fs::write(&path, "{\"blocks\": []}").unwrap();
+
+        let result =
+            FileOps::write_balance(&path, FileOps::parse(&path).unwrap(), String::from("a"), 10);
+        let data = FileOps::parse(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(FileOpsError::Schema(_))));
+        assert_eq!(serde_json::json!({ "blocks": [] }), data.unwrap());
+    }
+
+    #[test]
+    fn test_atomic_write_cleanup() {
+        // renaming a file over a directory fails
+        let path = temp_dir().join("mockchain_test_atomic_write_cleanup");
+        fs::create_dir_all(&path).unwrap();
+
+        let result = FileOps::overwrite(&path, &Value::Null);
+        let tmp_exists = FileOps::tmp_path(&path).exists();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert!(matches!(result, Err(FileOpsError::Io(_))));
+        assert!(!tmp_exists);
     }
 }
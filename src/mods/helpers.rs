@@ -1,19 +1,29 @@
+// std library
+use std::time::SystemTime;
+
 // 3rd party crates
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde_json::{to_value, Value};
 
 // imports
 use super::{
-    block::Block,
-    crypto::{get_merkle_root, hash_block, hash_transaction, KeyPair},
-    file::FileOps,
+    amount::Amount,
+    base::Blockchain,
+    block::{Block, BLOCK_REWARD, DIFFICULTY_WINDOW, TARGET_BLOCK_TIME_SECS},
+    config::Config,
+    crypto::{hash_block_header_with, hash_transaction, KeyPair},
+    events::{Event, LedgerEvents},
+    file::{FileOps, FileOpsError},
+    ledger::Ledger,
     log::{Log, LogLevel},
+    mempool,
     signing_data::Signing,
     transaction::Transaction,
     wallet::Wallet,
 };
-use crate::{BLOCKCHAIN_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH, WALLETS_PATH};
 
-/// Gets an RFC3339 timestamp
+/// Gets the current time as an RFC3339 timestamp,
+/// the format used by every block and log entry
 ///
 /// # Visibility
 /// public
@@ -25,10 +35,31 @@ use crate::{BLOCKCHAIN_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH
 /// ```
 /// String
 /// ```
-pub fn get_timestamp() -> String {
+pub fn now_rfc3339() -> String {
     let now = Utc::now();
-    let timestamp = now.to_rfc3339();
-    timestamp
+    now.to_rfc3339()
+}
+
+/// Parses an RFC3339 timestamp so block times can be
+/// compared and subtracted
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// timestamp: &str -> RFC3339 timestamp e.g. "2024-01-01T00:00:00+00:00"
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<SystemTime, String>
+/// ```
+pub fn parse_timestamp(timestamp: &str) -> Result<SystemTime, String> {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => Ok(SystemTime::from(time)),
+        Err(e) => Err(format!("Invalid timestamp '{}': {}", timestamp, e)),
+    }
 }
 
 /// Creates a wallet
@@ -38,22 +69,23 @@ pub fn get_timestamp() -> String {
 ///
 /// # Args
 /// ```
-/// name: String -> name of the account
+/// config: &Config -> locations of the data files
+/// name: String    -> name of the account
 /// ```
 /// # Returns
-/// Nothing
-pub fn create_wallet(name: String) {
-    let key_pair = KeyPair::generate(name.clone());
-    Log::new(LogLevel::INFO, 6, Some(vec![name.clone()]));
-    let address = key_pair.public_key.clone();
-    let wallet = Wallet {
-        name: name.clone(),
-        address,
-        balance: 0,
-    };
-    Log::new(LogLevel::INFO, 7, Some(vec![name]));
-    FileOps::write(KEYPAIRS_PATH.as_path(), "keypairs", key_pair);
-    FileOps::write(WALLETS_PATH.as_path(), "wallets", wallet);
+/// ```
+/// Result<Wallet, String>
+/// ```
+pub fn create_wallet(config: &Config, name: String) -> Result<Wallet, String> {
+    let result = Wallet::new(&config.wallets_path, &config.keypairs_path, name.clone());
+    match &result {
+        Ok(_) => {
+            Log::new(LogLevel::INFO, 6, Some(vec![name.clone()]));
+            Log::new(LogLevel::INFO, 7, Some(vec![name]));
+        }
+        Err(e) => Log::new(LogLevel::WARNING, 36, Some(vec![name, e.clone()])),
+    }
+    result
 }
 
 /// Creates a Transaction
@@ -63,23 +95,46 @@ pub fn create_wallet(name: String) {
 ///
 /// # Args
 /// ```
-/// from: String -> name of sender
-/// to: String   -> name of recipient
-/// amount: i32  -> amount
+/// config: &Config -> locations of the data files
+/// from: String    -> name of sender
+/// to: String      -> name of recipient
+/// amount: i64     -> amount
+/// fee: u32        -> fee paid to the miner
+/// passphrase: Option<&str> -> passphrase if the senders private key is encrypted
 /// ```
 ///
 /// # Returns
-/// Nothing
-pub fn create_transaction(from: String, to: String, amount: i32) {
+/// ```
+/// Result<(), String>
+/// ```
+pub fn create_transaction(
+    config: &Config,
+    from: String,
+    to: String,
+    amount: i64,
+    fee: u32,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
     Log::new(LogLevel::INFO, 19, None);
+    let amount = Amount::new(amount)?;
     // get wallet public keys
-    let from_address = match Wallet::get_wallet_address(&from) {
-        Some(key) => key.replace("\"", ""),
-        None => return,
+    let from_address = match Wallet::get_wallet_address(&FileOps {}, &config.wallets_path, &from) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Err(format!("No wallet found under name '{}'", from)),
+        Err(e @ FileOpsError::NotFound(_)) => return Err(e.to_string()),
+        Err(e) => {
+            Log::new_panic(LogLevel::ERROR, 12, Some(vec!["wallets.json".to_string()]));
+            panic!("Error parsing data file content at wallets.json: {}", e);
+        }
     };
-    let to_address = match Wallet::get_wallet_address(&to) {
-        Some(key) => key.replace("\"", ""),
-        None => return,
+    let to_address = match Wallet::get_wallet_address(&FileOps {}, &config.wallets_path, &to) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Err(format!("No wallet found under name '{}'", to)),
+        Err(e @ FileOpsError::NotFound(_)) => return Err(e.to_string()),
+        Err(e) => {
+            Log::new_panic(LogLevel::ERROR, 12, Some(vec!["wallets.json".to_string()]));
+            panic!("Error parsing data file content at wallets.json: {}", e);
+        }
     };
     Log::new(LogLevel::INFO, 20, Some(vec![from.clone(), to.clone()]));
 
@@ -87,180 +142,215 @@ pub fn create_transaction(from: String, to: String, amount: i32) {
     let hash = hash_transaction(&from_address, &to_address, &amount.to_string());
     Log::new(LogLevel::INFO, 21, None);
 
-    // get senders private key
-    let mut base_data = FileOps::parse(KEYPAIRS_PATH.as_path());
-    let key_data = match base_data["keypairs"].as_array_mut() {
-        Some(arr) => arr,
-        None => {
-            Log::new_panic(LogLevel::ERROR, 2, Some(vec!["keypairs.json".to_string()]));
-            panic!("Failed to read keypairs.json, has the data been modified or the file moved or deleted?");
-        }
-    };
-
-    let mut private_key = String::from("");
-    for key_pair in key_data {
-        if key_pair["name"] == from {
-            let key_value = match key_pair["private_key"].as_str() {
-                Some(val) => val,
-                None => {
-                    Log::new_panic(LogLevel::ERROR, 15, None);
-                    panic!(
-                        "Failed to parse private key from json_serde Value to &str while creating transaction"
-                    );
-                }
-            };
-            private_key.push_str(key_value);
-        }
+    // get senders private key, checked against the stored public key
+    let private_key = match passphrase {
+        Some(passphrase) => KeyPair::load_decrypted(&config.keypairs_path, &from, passphrase)?,
+        None => KeyPair::load(&config.keypairs_path, &from)?,
     }
+    .private_key;
     Log::new(LogLevel::INFO, 22, Some(vec![from.clone()]));
 
-    // get the transaction signature and the signing key
-    let (signature, signing_key) = KeyPair::sign(&hash, private_key);
+    let transaction = Transaction {
+        hash: hash.clone(),
+        txid: String::new(),
+        from_address,
+        to_address,
+        amount,
+        outputs: Vec::new(),
+        fee,
+        timestamp: now_rfc3339(),
+        signature: String::new(),
+    };
+    let mut transaction = match to_value(&transaction) {
+        Ok(val) => val,
+        Err(e) => {
+            Log::new_panic(LogLevel::ERROR, 10, Some(vec!["transactions".to_string()]));
+            panic!("Failed to parse given object to serde_json Value: {}", e);
+        }
+    };
+
+    // sign the canonical bytes and get the signing key
+    let (signature, signing_key) =
+        KeyPair::sign(&Transaction::canonical_bytes(&transaction), private_key);
     Log::new(LogLevel::INFO, 23, Some(vec![from.clone()]));
+    transaction["txid"] = Value::from(Transaction::txid(&transaction));
+    transaction["signature"] = Value::from(signature.clone());
 
     let signing_data = Signing {
         name: from,
         hash: hash.clone(),
         signing_key,
-        signature: signature.clone(),
-    };
-
-    let transaction = Transaction {
-        hash: hash.clone(),
-        from_address,
-        to_address,
-        amount,
-        signature: signature.clone(),
+        signature,
     };
 
-    // write objects to file
-    FileOps::write(TRANSACTIONS_PATH.as_path(), "transactions", transaction);
+    // the mempool only accepts a transaction signed by the sender
+    if let Err(e) = mempool::add(config, transaction) {
+        Log::new(LogLevel::WARNING, 35, Some(vec![e.clone()]));
+        return Err(e);
+    }
     Log::new(LogLevel::INFO, 25, None);
-    FileOps::write(SIGNING_DATA_PATH.as_path(), "signing_data", signing_data);
+    FileOps::write(&config.signing_data_path, "signing_data", signing_data);
     Log::new(LogLevel::INFO, 24, None);
+    Ok(())
 }
 
-/// Mine the next block in the chain
+/// Mine the next block in the chain from at most
+/// config.max_txs_per_block pending transactions, the
+/// rest are left in the mempool for the next block.
+/// Balances are worked out in memory while wallets.json
+/// is locked and written just before the block is added,
+/// then rolled back if the block cannot be added. On any
+/// error every wallet is left untouched and the taken
+/// transactions are put back in the mempool. A transaction
+/// the wallets reject, e.g. a sender who has since spent
+/// the funds or been frozen, is logged and discarded
 ///
 /// # Visibility
 /// public
 ///
 /// # Args
 /// ```
-/// name: String -> name on the miners account
+/// config: &Config -> locations of the data files
+/// name: String    -> name on the miners account
 /// ```
 /// # Returns
-///
-/// Nothing
-pub fn mine_block(name: String) {
+/// ```
+/// Result<(), String>
+/// ```
+pub fn mine_block(config: &Config, name: String) -> Result<(), String> {
     Log::new(LogLevel::INFO, 8, None);
-    let mut base_data = FileOps::parse(BLOCKCHAIN_PATH.as_path());
-    let blockchain = match base_data["blockchain"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["blockchain.json".to_string()]),
-            );
-            panic!("Failed to read blockchain.json, has the data been modified or the file moved or deleted?");
-        }
+    let path = config.wallets_path.as_path();
+    let miner = match Wallet::get_wallet_address(&FileOps {}, path, &name) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Err(format!("No wallet found under name '{}'", name)),
+        Err(e) => return Err(e.to_string()),
     };
-
-    let last_block = &blockchain[blockchain.len() - 1];
-    // components of Block hash
-    let mut nonce = 0;
-    let previous_hash = &last_block["hash"].to_string().replace("\"", "");
-    let mut base_data = FileOps::parse(TRANSACTIONS_PATH.as_path());
-    // set mining difficulty
-    let leading_zeros = String::from("0".repeat(2));
-    Log::new(
-        LogLevel::INFO,
-        9,
-        Some(vec![leading_zeros.len().to_string()]),
-    );
-    // get block hash
-    let mut hash = hash_block(
-        &nonce.to_string(),
-        &previous_hash.clone(),
-        &base_data.to_string(),
-    );
-
-    // compute the correct hash to mine a new Block (00...98de872911a5e etc)
-    while !hash.starts_with(&leading_zeros) {
-        nonce += 1;
-        hash = hash_block(
-            &nonce.to_string(),
-            &previous_hash.clone(),
-            &base_data.to_string(),
-        );
+    let (updates, payout) = FileOps::with_lock(path, || {
+        let ledger = FileOps::open(path).map_err(|e| e.to_string())?;
+        // take as many pending transactions as fit in one block
+        let transactions =
+            mempool::take_prioritized(&config.transactions_path, config.max_txs_per_block);
+        let mined = mine_transactions(config, ledger, &miner, &transactions);
+        if let Err(e) = &mined {
+            mempool::put_back(&config.transactions_path, &transactions).map_err(|put_back| {
+                format!(
+                    "{}, the taken transactions could not be put back: {}",
+                    e, put_back
+                )
+            })?;
+        }
+        mined
+    })?;
+    for (address, balance) in updates {
+        LedgerEvents::emit(Event::BalanceUpdated {
+            path: path.to_path_buf(),
+            address,
+            balance,
+        });
     }
-    Log::new(LogLevel::INFO, 10, None);
-
-    // get the current timestamp
-    let timestamp = get_timestamp();
-
-    // get the merkle root of this Blocks Transactions
-    let merkle_root = get_merkle_root(TRANSACTIONS_PATH.as_path());
-    Log::new(LogLevel::INFO, 11, None);
+    Log::new(LogLevel::INFO, 17, Some(vec![payout.to_string(), name]));
+    Log::new(LogLevel::INFO, 18, None);
+    Ok(())
+}
 
-    // pay all transactions
-    let transactions = match base_data["transactions"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["blockchain.json".to_string()]),
-            );
-            panic!("Failed to read blockchain.json, has the data been modified or the file moved or deleted?");
-        }
-    };
+/// Builds a block from taken transactions, commits the
+/// new balances to wallets.json and adds the block to
+/// the chain. wallets.json is restored to the ledger as
+/// it was given if the block cannot be added. The caller
+/// must hold the wallets.json lock
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config         -> locations of the data files
+/// ledger: Ledger          -> wallets.json before the block
+/// miner: &str             -> address paid the coinbase
+/// transactions: &[Value]  -> transactions taken for the block
+/// ```
+/// # Returns
+/// ```
+/// Result<(Vec<(String, i64)>, i64), String> -> balances updated and the coinbase paid
+/// ```
+fn mine_transactions(
+    config: &Config,
+    mut ledger: Ledger,
+    miner: &str,
+    transactions: &[Value],
+) -> Result<(Vec<(String, i64)>, i64), String> {
+    let original = ledger.clone();
 
+    // apply all transactions, keeping only those accepted for the block
+    let mut accepted = Vec::new();
+    let mut updates = Vec::new();
     for t in transactions {
-        let amount: i32;
-        if let Some(val) = t["amount"].as_i64() {
-            amount = val as i32;
-        } else {
-            Log::new_panic(LogLevel::ERROR, 16, None);
-            panic!("Failed to parse transaction amount while mining block");
-        };
+        let outputs = Transaction::outputs(t).unwrap_or_default();
+        let paid: i64 = outputs.iter().map(|o| o.amount).sum();
+        let recipients = outputs
+            .iter()
+            .map(|o| o.to.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
 
-        if t["from_address"] == "REWARD" {
-            Log::new(LogLevel::INFO, 12, Some(vec![t["to_address"].to_string()]));
-            Wallet::update_balance(t["to_address"].to_string(), amount, "add");
-        } else {
-            Wallet::update_balance(t["to_address"].to_string(), amount, "add");
-            Wallet::update_balance(t["from_address"].to_string(), amount, "subtract");
-            Log::new(
-                LogLevel::INFO,
-                13,
+        match ledger.apply_transaction(t) {
+            Ok(balances) => {
+                updates.extend(balances);
+                accepted.push(t.clone());
+                Log::new(
+                    LogLevel::INFO,
+                    13,
+                    Some(vec![
+                        paid.to_string(),
+                        t["from_address"].to_string(),
+                        recipients,
+                    ]),
+                )
+            }
+            Err(e) => Log::new(
+                LogLevel::WARNING,
+                34,
                 Some(vec![
-                    t["amount"].to_string(),
+                    paid.to_string(),
                     t["from_address"].to_string(),
-                    t["to_address"].to_string(),
+                    recipients,
+                    e,
                 ]),
-            )
+            ),
         }
     }
     Log::new(LogLevel::INFO, 14, None);
 
-    let block = Block {
-        timestamp,
-        hash,
-        previous_hash: previous_hash.to_string(),
-        nonce,
-        transactions: base_data,
-        merkle_root,
-    };
+    // add the coinbase and get the merkle root of this Blocks Transactions
+    let mut block = Block::new(String::new(), accepted);
+    block.add_coinbase(miner, BLOCK_REWARD);
+    let payout = block.transaction_list()[0]["amount"].as_i64().unwrap_or(0);
+    updates.push((
+        miner.to_string(),
+        ledger.update_balance(miner, payout, "add")?,
+    ));
+    Log::new(LogLevel::INFO, 11, None);
+
+    // link to the chain tip and compute the correct hash (00...98de872911a5e etc)
+    let difficulty = Blockchain::adjust_difficulty(
+        &config.blockchain_path,
+        TARGET_BLOCK_TIME_SECS,
+        DIFFICULTY_WINDOW,
+    );
+    Log::new(LogLevel::INFO, 9, Some(vec![difficulty.to_string()]));
 
-    FileOps::write(BLOCKCHAIN_PATH.as_path(), "blockchain", block);
+    // write the balances first so a failed write leaves the chain untouched
+    ledger.commit().map_err(|e| e.to_string())?;
+    if let Err(e) = Blockchain::add_block(&config.blockchain_path, block, difficulty) {
+        original
+            .commit()
+            .map_err(|restore| format!("{}, wallets.json could not be restored: {}", e, restore))?;
+        return Err(e);
+    }
+    Log::new(LogLevel::INFO, 10, None);
     Log::new(LogLevel::INFO, 15, None);
-    Transaction::clear();
     Log::new(LogLevel::INFO, 16, None);
-    Transaction::add_reward(name);
-    Log::new(LogLevel::INFO, 17, None);
-    Log::new(LogLevel::INFO, 18, None);
+    Ok((updates, payout))
 }
 
 /// Verifies the integrity of the blockchain
@@ -269,15 +359,28 @@ pub fn mine_block(name: String) {
 /// public
 ///
 /// # Args
-/// None
+/// ```
+/// config: &Config -> locations of the data files
+/// ```
 ///
 /// # Returns
 /// ```
 /// bool
 /// ```
-pub fn verify_chain() -> bool {
+pub fn verify_chain(config: &Config) -> bool {
     Log::new(LogLevel::INFO, 26, None);
-    let mut bc_base_data = FileOps::parse(BLOCKCHAIN_PATH.as_path());
+    let algo = Blockchain::hash_algo(&config.blockchain_path).unwrap_or_default();
+    let mut bc_base_data = match FileOps::parse(&config.blockchain_path) {
+        Ok(data) => data,
+        Err(e) => {
+            Log::new_panic(
+                LogLevel::ERROR,
+                12,
+                Some(vec!["blockchain.json".to_string()]),
+            );
+            panic!("Error parsing data file content at blockchain.json: {}", e);
+        }
+    };
     let blockchain = match bc_base_data["blockchain"].as_array_mut() {
         Some(data) => data,
         None => {
@@ -306,10 +409,12 @@ pub fn verify_chain() -> bool {
         }
 
         // validate the current block hash
-        let hash = hash_block(
-            &current_block["nonce"].to_string(),
+        let hash = hash_block_header_with(
+            algo,
+            &current_block["timestamp"].to_string().replace("\"", ""),
             &current_block["previous_hash"].to_string().replace("\"", ""),
-            &current_block["transactions"].to_string(),
+            &current_block["nonce"].to_string(),
+            &current_block["merkle_root"].to_string().replace("\"", ""),
         );
 
         if current_block["hash"].to_string().replace("\"", "") != hash {
@@ -336,23 +441,23 @@ pub fn verify_chain() -> bool {
             }
         };
 
-        for j in 0..transactions.len() {
+        for transaction in transactions.iter() {
             // validate current transaction hash
-            let t_hash = hash_transaction(
-                &transactions[j]["from_address"]
-                    .to_string()
-                    .replace("\"", ""),
-                &transactions[j]["to_address"].to_string().replace("\"", ""),
-                &transactions[j]["amount"].to_string(),
-            );
+            let t_hash = Transaction::content_hash(transaction);
 
-            if transactions[j]["hash"] != t_hash.clone() {
+            if transaction["hash"] != t_hash.clone() {
                 Log::new(LogLevel::ERROR, 29, None);
                 return false;
             }
 
             // get signing key for this transaction
-            let mut sd_base_data = FileOps::parse(SIGNING_DATA_PATH.as_path());
+            let mut sd_base_data = match FileOps::parse(&config.signing_data_path) {
+                Ok(data) => data,
+                Err(e) => {
+                    Log::new_panic(LogLevel::ERROR, 12, Some(vec!["signing.json".to_string()]));
+                    panic!("Error parsing data file content at signing.json: {}", e);
+                }
+            };
             let signing_data = match sd_base_data["signing_data"].as_array_mut() {
                 Some(data) => data,
                 None => {
@@ -368,7 +473,8 @@ pub fn verify_chain() -> bool {
                         s["signature"].to_string().replace("\"", ""),
                         s["signing_key"].to_string().replace("\"", ""),
                     );
-                    if !KeyPair::verify(signature, signing_key, t_hash.clone()) {
+                    let message = Transaction::canonical_bytes(transaction);
+                    if !KeyPair::verify(signature, signing_key, &message) {
                         Log::new(LogLevel::ERROR, 31, Some(vec![s["name"].to_string()]));
                         return false;
                     }
@@ -0,0 +1,257 @@
+/*
+    Cached handle over wallets.json so repeated lookups
+    only parse the file once
+*/
+
+// std library
+use std::path::{Path, PathBuf};
+
+// 3rd party crates
+use serde_json::Value;
+
+// imports
+use super::{
+    file::{FileOps, FileOpsError},
+    wallet::Wallet,
+};
+
+/// Defines an in-memory copy of wallets.json. Reads
+/// are served from the cached data and updates are
+/// only written back to file on commit()
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// path: PathBuf -> path to wallets.json
+/// data: Value   -> parsed file content
+/// ```
+///
+/// # Derives
+/// ```
+/// Clone, Debug
+/// ```
+#[derive(Clone, Debug)]
+pub struct Ledger {
+    path: PathBuf,
+    data: Value,
+}
+
+impl FileOps {
+    /// Parses wallets.json once and returns a Ledger
+    /// handle over the parsed data
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Ledger, FileOpsError>
+    /// ```
+    pub fn open(path: &Path) -> Result<Ledger, FileOpsError> {
+        Ok(Ledger {
+            path: path.to_path_buf(),
+            data: FileOps::validate(FileOps::parse(path)?, "wallets")?,
+        })
+    }
+}
+
+impl Ledger {
+    /// Checks to see if a name has already been used
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name to check for
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn name_exists(&self, name: &str) -> bool {
+        self.find(|w| w["name"] == name).is_some()
+    }
+
+    /// Gets the public key address of a wallet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of account to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<String>
+    /// ```
+    pub fn address(&self, name: &str) -> Option<String> {
+        self.find(|w| w["name"] == name)
+            .and_then(|w| w["address"].as_str())
+            .map(String::from)
+    }
+
+    /// Gets the current balance of a wallet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of account to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<i64>
+    /// ```
+    pub fn balance(&self, name: &str) -> Option<i64> {
+        self.find(|w| w["name"] == name)
+            .and_then(|w| w["balance"].as_i64())
+    }
+
+    /// Adds to or subtracts from the cached balance of a
+    /// wallet with the same checks as Wallet::update_balance.
+    /// Nothing is written until commit()
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// address: &str -> wallet address to update
+    /// amount: i64   -> amount to add or subtract
+    /// op: &str      -> "add" | "subtract"
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i64, String> -> the new balance
+    /// ```
+    pub fn update_balance(&mut self, address: &str, amount: i64, op: &str) -> Result<i64, String> {
+        Wallet::apply_balance(&mut self.data, address, amount, op)
+    }
+
+    /// Applies a mined transaction to the cached balances,
+    /// see Wallet::apply_transaction. Nothing is written
+    /// until commit()
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value -> transaction JSON to apply
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<(String, i64)>, String> -> address and new balance of each wallet updated
+    /// ```
+    pub fn apply_transaction(&mut self, tx: &Value) -> Result<Vec<(String, i64)>, String> {
+        Wallet::apply_transaction(&mut self.data, tx)
+    }
+
+    /// Writes the cached data back to wallets.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), FileOpsError>
+    /// ```
+    pub fn commit(&self) -> Result<(), FileOpsError> {
+        FileOps::with_lock(&self.path, || FileOps::overwrite(&self.path, &self.data))
+    }
+
+    /// Finds the first cached wallet matching a predicate
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// predicate: P -> test applied to each wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Option<&Value>
+    /// ```
+    fn find<P: Fn(&Value) -> bool>(&self, predicate: P) -> Option<&Value> {
+        self.data["wallets"]
+            .as_array()?
+            .iter()
+            .find(|w| predicate(w))
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_ledger {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::mods::wallet::Wallet;
+
+    #[test]
+    fn test_ledger_matches_static_methods() {
+        let path = temp_dir().join("mockchain_test_ledger.json");
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": "a".repeat(130), "balance": 100 },
+            { "name": "bob", "address": "b".repeat(130), "balance": 5 },
+        ]});
+        fs::write(&path, wallets.to_string()).unwrap();
+
+        let mut ledger = FileOps::open(&path).unwrap();
+        for name in ["alice", "bob", "carol"] {
+            assert_eq!(
+                Wallet::name_exists(&FileOps {}, &path, name).unwrap(),
+                ledger.name_exists(name)
+            );
+            assert_eq!(
+                Wallet::get_balance(&FileOps {}, &path, name).unwrap(),
+                ledger.balance(name)
+            );
+            assert_eq!(
+                Wallet::get_wallet_address(&FileOps {}, &path, name).unwrap(),
+                ledger.address(name)
+            );
+        }
+
+        let cached = ledger.update_balance(&"b".repeat(130), 10, "subtract");
+        let stored = Wallet::update_balance(&path, "b".repeat(130), 10, "subtract");
+        assert_eq!(stored, cached);
+
+        ledger.update_balance(&"a".repeat(130), 25, "add").unwrap();
+        // nothing is written until commit
+        assert_eq!(
+            Some(100),
+            Wallet::get_balance(&FileOps {}, &path, "alice").unwrap()
+        );
+        ledger.commit().unwrap();
+        let committed = Wallet::get_balance(&FileOps {}, &path, "alice").unwrap();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Some(125), committed);
+    }
+}
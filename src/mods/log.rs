@@ -7,7 +7,7 @@ use std::path::Path;
 use phf::phf_map;
 
 // imports
-use super::{helpers::get_timestamp, messaging::replace};
+use super::{helpers::now_rfc3339, messaging::replace};
 use crate::{LOG_FILE_PATH, LOG_PATH};
 
 static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
@@ -27,7 +27,7 @@ static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
     14u8 => "...all pending transactions including rewards processed and paid",
     15u8 => "...new block appended to blockchain successfully",
     16u8 => "...transaction data cleared",
-    17u8 => "...new reward transaction appended to 'transactions.json'",
+    17u8 => "...coinbase of {} tokens paid to {}",
     18u8 => "...mining complete",
     19u8 => "Processing new transaction...",
     20u8 => "...read {}'s and {}'s public keys to start transaction",
@@ -44,6 +44,9 @@ static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
     31u8 => "...bad transaction signature in chain; a transaction signed by {} could not be verified using ECDSA verification, verification failed",
     32u8 => "...transaction signatures are consistent",
     33u8 => "...blockchain verification completed successfully",
+    34u8 => "...transaction rejected; {} tokens could not be sent from {} to {}: {}",
+    35u8 => "...transaction rejected before entering the mempool: {}",
+    36u8 => "Wallet could not be created for '{}': {}",
 };
 
 static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
@@ -63,6 +66,8 @@ static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
     14u8 => "Failed to parse empty transactions object to serde_json Value::String before clearing",
     15u8 => "Failed to parse private key from json_serde Value to &str while creating transaction",
     16u8 => "Failed to parse transaction amount while mining block",
+    17u8 => "Failed to update wallet balance while mining block: {}",
+    18u8 => "Failed to acquire lock on {}",
 };
 
 /// Log enum with log level states
@@ -76,6 +81,7 @@ static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
 /// Warning
 /// Error
 /// ```
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub enum LogLevel {
     INFO,
@@ -115,10 +121,11 @@ impl Log {
     ///
     /// # Returns
     /// Nothing
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(level: LogLevel, msg_key: u8, args: Option<Vec<String>>) {
         Log::record(&Log {
             level,
-            timestamp: get_timestamp(),
+            timestamp: now_rfc3339(),
             message: Log::get_log_message(msg_key, args, false),
         });
     }
@@ -140,7 +147,7 @@ impl Log {
     pub fn new_panic(level: LogLevel, msg_key: u8, args: Option<Vec<String>>) {
         Log::record(&Log {
             level,
-            timestamp: get_timestamp(),
+            timestamp: now_rfc3339(),
             message: Log::get_log_message(msg_key, args, true),
         });
     }
@@ -164,7 +171,8 @@ impl Log {
         }
     }
 
-    /// Writes an entry to the log
+    /// Writes an entry to the log file and forwards it to
+    /// the `log` facade so any installed logger sees it too
     ///
     /// # Visibility
     /// public
@@ -175,6 +183,13 @@ impl Log {
     /// # Returns
     /// Nothing
     pub fn record(&self) {
+        let level = match self.level {
+            LogLevel::INFO => ::log::Level::Info,
+            LogLevel::WARNING => ::log::Level::Warn,
+            LogLevel::ERROR => ::log::Level::Error,
+        };
+        ::log::log!(level, "{}", self.message);
+
         let mut log_file = match fs::OpenOptions::new()
             .create(true)
             .append(true)
@@ -230,8 +245,8 @@ impl Log {
         let helper = |map: &phf::Map<u8, &str>| {
             if let Some(msg) = map.get(&key) {
                 match args {
-                    Some(vec) => return replace(msg.to_string(), vec),
-                    None => return msg.to_string(),
+                    Some(vec) => replace(msg.to_string(), vec),
+                    None => msg.to_string(),
                 }
             } else {
                 none_log.clone()
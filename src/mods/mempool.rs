@@ -0,0 +1,639 @@
+/*
+    Staging area for pending transactions backed by
+    transactions.json
+*/
+
+// std library
+use std::cmp::Reverse;
+use std::path::Path;
+
+// 3rd party crates
+use serde_json::{json, Value};
+
+// imports
+use super::{
+    config::Config,
+    events::{Event, LedgerEvents},
+    file::FileOps,
+    transaction::Transaction,
+    wallet::Wallet,
+};
+
+/// Adds a pending transaction to the mempool. Only a
+/// transaction signed by its sender is accepted. The
+/// txid is worked out from the transaction contents and
+/// the transaction is rejected if that txid is already
+/// pending or mined, so the same transaction can never be
+/// replayed. A transaction paying an address without a
+/// wallet, or one the sender cannot afford on top of
+/// their other pending transactions, is rejected
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// tx: Value       -> transaction to stage
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), String>
+/// ```
+pub fn add(config: &Config, mut tx: Value) -> Result<(), String> {
+    if !tx.is_object() {
+        return Err(format!("Transaction must be a JSON object, found {}", tx));
+    }
+    Transaction::verify(&tx, &config.wallets_path)?;
+    check_recipients(&tx, &config.wallets_path)?;
+    let txid = Transaction::txid(&tx);
+    match tx["txid"].as_str() {
+        Some(given) if given != txid => {
+            return Err(format!(
+                "Transaction txid {} does not match its contents",
+                given
+            ))
+        }
+        _ => tx["txid"] = Value::from(txid.clone()),
+    }
+
+    let path = config.transactions_path.as_path();
+    FileOps::with_lock(path, || {
+        let mut base_data = FileOps::parse(path).map_err(|e| e.to_string())?;
+        let pending = match base_data["transactions"].as_array_mut() {
+            Some(data) => data,
+            None => return Err("Failed to read transactions.json".to_string()),
+        };
+        if pending.iter().any(|t| Transaction::txid(t) == txid) {
+            return Err(format!("Transaction {} is already pending", txid));
+        }
+        if is_mined(&config.blockchain_path, &txid) {
+            return Err(format!("Transaction {} has already been mined", txid));
+        }
+        Transaction::check_spendable(&tx, &config.wallets_path, path)?;
+        pending.push(tx);
+        FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())
+    })?;
+    LedgerEvents::emit(Event::TransactionAccepted {
+        path: path.to_path_buf(),
+        txid,
+    });
+    Ok(())
+}
+
+/// Checks every output of a transaction pays an
+/// address that has a wallet, so a block can never hold
+/// a payment that has nowhere to go
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// tx: &Value          -> transaction JSON to check
+/// wallets_path: &Path -> path to wallets.json
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), String>
+/// ```
+fn check_recipients(tx: &Value, wallets_path: &Path) -> Result<(), String> {
+    let wallets = Wallet::list_all(&FileOps {}, wallets_path).map_err(|e| e.to_string())?;
+    for output in Transaction::outputs(tx)? {
+        if !wallets.iter().any(|w| w.address == output.to) {
+            return Err(format!("No wallet found with address {}", output.to));
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether a transaction with the given txid
+/// is already in a block on the chain
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// blockchain_path: &Path -> path to blockchain.json
+/// txid: &str             -> txid to look for
+/// ```
+///
+/// # Returns
+/// ```
+/// bool
+/// ```
+fn is_mined(blockchain_path: &Path, txid: &str) -> bool {
+    let base_data = match FileOps::parse(blockchain_path) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    base_data["blockchain"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|block| block["transactions"]["transactions"].as_array())
+        .flatten()
+        .any(|t| Transaction::txid(t) == txid)
+}
+
+/// Removes up to `max` pending transactions, oldest
+/// first, and returns them for the next block. The
+/// remaining transactions are written back under the
+/// same lock so a taken transaction is never left pending
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// path: &Path -> path to transactions.json
+/// max: usize  -> maximum number of transactions to take
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<Value> -> empty if transactions.json cannot be read
+/// ```
+#[allow(dead_code)]
+pub fn take(path: &Path, max: usize) -> Vec<Value> {
+    take_positions(path, |pending| (0..max.min(pending.len())).collect())
+}
+
+/// Removes up to `max` pending transactions with the
+/// highest fees and returns them ordered by descending
+/// fee, ties broken by ascending txid, so every node
+/// builds the same block and merkle root from the same
+/// mempool. The rest stay pending in their original order
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// path: &Path -> path to transactions.json
+/// max: usize  -> maximum number of transactions to take
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<Value> -> empty if transactions.json cannot be read
+/// ```
+pub fn take_prioritized(path: &Path, max: usize) -> Vec<Value> {
+    take_positions(path, |pending| {
+        let mut order: Vec<(Reverse<u64>, String, usize)> = pending
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| {
+                let fee = tx["fee"].as_u64().unwrap_or(0);
+                (Reverse(fee), Transaction::txid(tx), i)
+            })
+            .collect();
+        order.sort();
+        order.truncate(max);
+        order.into_iter().map(|(_, _, i)| i).collect()
+    })
+}
+
+/// Removes the pending transactions at the positions a
+/// selector picks and returns them in the order picked.
+/// The rest are written back in their original order
+/// under the same lock
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// path: &Path -> path to transactions.json
+/// select: F   -> picks the positions to take from the pending transactions
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<Value> -> empty if transactions.json cannot be read
+/// ```
+fn take_positions<F>(path: &Path, select: F) -> Vec<Value>
+where
+    F: FnOnce(&[Value]) -> Vec<usize>,
+{
+    FileOps::with_lock(path, || {
+        let base_data = match FileOps::parse(path) {
+            Ok(data) => data,
+            Err(_) => return Vec::new(),
+        };
+        let pending = match base_data["transactions"].as_array() {
+            Some(data) => data,
+            None => return Vec::new(),
+        };
+        let positions = select(pending);
+
+        let selected: Vec<Value> = positions.iter().map(|i| pending[*i].clone()).collect();
+        let remaining: Vec<&Value> = pending
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !positions.contains(i))
+            .map(|(_, tx)| tx)
+            .collect();
+        match FileOps::overwrite(path, &json!({ "transactions": remaining })) {
+            Ok(_) => selected,
+            Err(_) => Vec::new(),
+        }
+    })
+}
+
+/// Returns taken transactions to the front of the
+/// mempool, e.g. when the block they were taken for
+/// could not be added to the chain. Transactions that
+/// are still pending are kept after them
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// path: &Path            -> path to transactions.json
+/// transactions: &[Value] -> transactions to put back
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), String>
+/// ```
+pub fn put_back(path: &Path, transactions: &[Value]) -> Result<(), String> {
+    FileOps::with_lock(path, || {
+        let base_data = FileOps::parse_validated(path, "transactions")?;
+        let pending = base_data["transactions"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+        let restored: Vec<&Value> = transactions.iter().chain(pending).collect();
+        FileOps::overwrite(path, &json!({ "transactions": restored })).map_err(|e| e.to_string())
+    })
+}
+
+/// Counts the transactions waiting to be mined
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// path: &Path -> path to transactions.json
+/// ```
+///
+/// # Returns
+/// ```
+/// usize
+/// ```
+pub fn pending_count(path: &Path) -> usize {
+    match FileOps::parse(path) {
+        Ok(data) => data["transactions"].as_array().map_or(0, |t| t.len()),
+        Err(_) => 0,
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_mempool {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use crate::mods::{base::Blockchain, block::Block, crypto::KeyPair, helpers::mine_block};
+
+    fn setup(dir_name: &str, balance: i64) -> (Config, KeyPair) {
+        let config = Config::new(&temp_dir().join(dir_name));
+        FileOps::init(&config, false);
+        let alice = KeyPair::from_seed(String::from("alice"), &[1u8; 32]);
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": alice.public_key, "balance": balance },
+            { "name": "bob", "address": "b", "balance": 0 },
+        ]});
+        fs::write(&config.wallets_path, wallets.to_string()).unwrap();
+        (config, alice)
+    }
+
+    fn signed(sender: &KeyPair, amount: i64, fee: u32) -> Value {
+        let mut tx = json!({
+            "from_address": sender.public_key,
+            "to_address": "b",
+            "amount": amount,
+            "fee": fee,
+        });
+        tx["hash"] = Value::from(Transaction::content_hash(&tx));
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            sender.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+        tx
+    }
+
+    #[test]
+    fn test_take_prioritized() {
+        let (config, alice) = setup("mockchain_test_mempool_prioritized", 100);
+        let path = config.transactions_path.clone();
+        let fees = [1, 5, 0, 5, 3];
+        for (i, fee) in fees.iter().enumerate() {
+            add(&config, signed(&alice, i as i64 + 1, *fee)).unwrap();
+        }
+        let txids: Vec<String> = FileOps::parse(&path).unwrap()["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(Transaction::txid)
+            .collect();
+
+        let taken = take_prioritized(&path, 3);
+        let next = take_prioritized(&path, 10);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        let (first, second) = if txids[1] < txids[3] { (1, 3) } else { (3, 1) };
+        let taken: Vec<String> = taken.iter().map(Transaction::txid).collect();
+        let next: Vec<String> = next.iter().map(Transaction::txid).collect();
+        assert_eq!(
+            vec![
+                txids[first].clone(),
+                txids[second].clone(),
+                txids[4].clone()
+            ],
+            taken
+        );
+        assert_eq!(vec![txids[0].clone(), txids[2].clone()], next);
+    }
+
+    #[test]
+    fn test_max_txs_per_block() {
+        let (mut config, alice) = setup("mockchain_test_mempool_block_size", 100);
+        config.max_txs_per_block = 2;
+        for i in 0..5 {
+            add(&config, signed(&alice, i + 1, 0)).unwrap();
+        }
+
+        let mut blocks = 0;
+        while pending_count(&config.transactions_path) > 0 {
+            mine_block(&config, String::from("bob")).unwrap();
+            blocks += 1;
+        }
+        let chain = FileOps::parse(&config.blockchain_path).unwrap();
+        let bob = Wallet::get_balance(&FileOps {}, &config.wallets_path, "bob").unwrap();
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        let sizes: Vec<usize> = chain["blockchain"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|block| {
+                block["transactions"]["transactions"]
+                    .as_array()
+                    .unwrap()
+                    .len()
+            })
+            .collect();
+        assert_eq!(3, blocks);
+        // each block also holds its coinbase
+        assert_eq!(vec![0, 3, 3, 2], sizes);
+        assert_eq!(Some(15 + 3 * 50), bob);
+    }
+
+    #[test]
+    fn test_mine_block_failed() {
+        let (config, alice) = setup("mockchain_test_mempool_mine_failed", 100);
+        for i in 0..3 {
+            add(&config, signed(&alice, i + 1, 1)).unwrap();
+        }
+        let before = FileOps::parse(&config.transactions_path).unwrap();
+        // a chain that cannot be read stops the block from being added
+        fs::write(
+            &config.blockchain_path,
+            json!({ "blockchain": "" }).to_string(),
+        )
+        .unwrap();
+
+        let result = mine_block(&config, String::from("bob"));
+        let after = FileOps::parse(&config.transactions_path).unwrap();
+        let balance = |name| {
+            Wallet::get_balance(&FileOps {}, &config.wallets_path, name)
+                .unwrap()
+                .unwrap()
+        };
+        let balances = (balance("alice"), balance("bob"));
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!((100, 0), balances);
+        let txids = |data: &Value| -> Vec<String> {
+            let mut txids: Vec<String> = data["transactions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(Transaction::txid)
+                .collect();
+            txids.sort();
+            txids
+        };
+        assert_eq!(txids(&before), txids(&after));
+    }
+
+    #[test]
+    fn test_mine_block_corrupt_wallets() {
+        let (config, alice) = setup("mockchain_test_mempool_mine_corrupt", 100);
+        for i in 0..3 {
+            add(&config, signed(&alice, i + 1, 1)).unwrap();
+        }
+        let wallets = fs::read_to_string(&config.wallets_path).unwrap();
+        fs::write(&config.wallets_path, &wallets[..wallets.len() / 2]).unwrap();
+        let unreadable = mine_block(&config, String::from("bob"));
+        let after_unreadable = pending_count(&config.transactions_path);
+
+        // the coinbase cannot be paid to a miner without a readable balance
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": alice.public_key, "balance": 100 },
+            { "name": "bob", "address": "b", "balance": "zero" },
+        ]});
+        fs::write(&config.wallets_path, wallets.to_string()).unwrap();
+        let unpayable = mine_block(&config, String::from("bob"));
+        let after_unpayable = pending_count(&config.transactions_path);
+        let stored = FileOps::parse(&config.wallets_path).unwrap();
+        let mined = Blockchain::get_block_by_index(&config.blockchain_path, 1);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert!(unreadable.is_err());
+        assert_eq!(3, after_unreadable);
+        assert!(unpayable.is_err());
+        assert_eq!(3, after_unpayable);
+        assert_eq!(wallets, stored);
+        assert!(mined.is_none());
+    }
+
+    #[test]
+    fn test_unknown_recipient() {
+        let (config, alice) = setup("mockchain_test_mempool_unknown_recipient", 100);
+        let mut tx = json!({ "from_address": alice.public_key, "to_address": "zz", "amount": 10 });
+        tx["hash"] = Value::from(Transaction::content_hash(&tx));
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            alice.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+
+        let rejected = add(&config, tx.clone());
+        // a transaction written to the mempool by hand is skipped when mined
+        let paid = signed(&alice, 5, 0);
+        let pending = json!({ "transactions": [tx, paid] });
+        fs::write(&config.transactions_path, pending.to_string()).unwrap();
+        let mined = mine_block(&config, String::from("bob"));
+        let block = Blockchain::get_block_by_index(&config.blockchain_path, 1).unwrap();
+        let balance = |name| {
+            Wallet::get_balance(&FileOps {}, &config.wallets_path, name)
+                .unwrap()
+                .unwrap()
+        };
+        let balances = (balance("alice"), balance("bob"));
+        let remaining = pending_count(&config.transactions_path);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(Err("No wallet found with address zz".to_string()), rejected);
+        assert_eq!(Ok(()), mined);
+        assert_eq!(2, block.transaction_list().len());
+        assert_eq!(paid["hash"], block.transaction_list()[1]["hash"]);
+        assert_eq!((95, 55), balances);
+        assert_eq!(0, remaining);
+    }
+
+    #[test]
+    fn test_add_unsigned() {
+        let (config, alice) = setup("mockchain_test_mempool_unsigned", 100);
+        let mallory = KeyPair::from_seed(String::from("mallory"), &[2u8; 32]);
+        let mut unsigned = signed(&alice, 1, 0);
+        unsigned["signature"] = Value::from("");
+        let mut forged = unsigned.clone();
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&forged),
+            mallory.private_key.clone(),
+        );
+        forged["signature"] = Value::from(signature);
+
+        let missing = add(&config, unsigned);
+        let rejected = add(&config, forged);
+        let count = pending_count(&config.transactions_path);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert!(missing
+            .unwrap_err()
+            .starts_with("Signature could not be verified"));
+        assert!(rejected
+            .unwrap_err()
+            .starts_with("Signature could not be verified"));
+        assert_eq!(0, count);
+    }
+
+    #[test]
+    fn test_add_duplicate() {
+        let (config, alice) = setup("mockchain_test_mempool_duplicate", 100);
+        let tx = signed(&alice, 1, 0);
+        add(&config, tx.clone()).unwrap();
+
+        let result = add(&config, tx.clone());
+        let count = pending_count(&config.transactions_path);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(
+            Err(format!(
+                "Transaction {} is already pending",
+                Transaction::txid(&tx)
+            )),
+            result
+        );
+        assert_eq!(1, count);
+    }
+
+    #[test]
+    fn test_add_repeat_payment() {
+        let (config, alice) = setup("mockchain_test_mempool_repeat", 100);
+        let sign = |timestamp: &str| {
+            let mut tx = signed(&alice, 10, 0);
+            tx["timestamp"] = Value::from(timestamp);
+            let (signature, _) = KeyPair::sign(
+                &Transaction::canonical_bytes(&tx),
+                alice.private_key.clone(),
+            );
+            tx["signature"] = Value::from(signature);
+            tx
+        };
+        add(&config, sign("2024-06-01T00:00:00+00:00")).unwrap();
+        mine_block(&config, String::from("bob")).unwrap();
+
+        let repeat = add(&config, sign("2024-06-01T00:05:00+00:00"));
+        let replayed = add(&config, sign("2024-06-01T00:00:00+00:00"));
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(Ok(()), repeat);
+        assert!(replayed.unwrap_err().ends_with("has already been mined"));
+    }
+
+    #[test]
+    fn test_take() {
+        let (config, alice) = setup("mockchain_test_mempool_take", 100);
+        let path = config.transactions_path.clone();
+        let txs: Vec<Value> = (0..5).map(|i| signed(&alice, i + 1, 0)).collect();
+        for tx in &txs {
+            add(&config, tx.clone()).unwrap();
+        }
+
+        let taken = take(&path, 3);
+        let remaining = pending_count(&path);
+        let next = take(&path, 10);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(3, taken.len());
+        assert_eq!(2, remaining);
+        assert_eq!(txs[0]["hash"], taken[0]["hash"]);
+        assert_eq!(txs[3]["hash"], next[0]["hash"]);
+    }
+
+    #[test]
+    fn test_add_mined_duplicate() {
+        let (config, alice) = setup("mockchain_test_mempool_mined_duplicate", 100);
+        let tx = signed(&alice, 1, 0);
+        add(&config, tx.clone()).unwrap();
+        let block = Block::new(String::new(), take(&config.transactions_path, 1));
+        Blockchain::add_block(&config.blockchain_path, block, 1).unwrap();
+
+        let replayed = add(&config, tx);
+        let mut tampered = signed(&alice, 2, 0);
+        tampered["txid"] = Value::from(Transaction::txid(&signed(&alice, 1, 0)));
+        let mismatched = add(&config, tampered);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert!(replayed.unwrap_err().ends_with("has already been mined"));
+        assert!(mismatched
+            .unwrap_err()
+            .ends_with("does not match its contents"));
+    }
+
+    #[test]
+    fn test_add_double_spend() {
+        let (config, alice) = setup("mockchain_test_mempool_double_spend", 30);
+        let first = signed(&alice, 20, 1);
+        let second = signed(&alice, 10, 0);
+
+        let accepted = add(&config, first);
+        let rejected = add(&config, second);
+        let count = pending_count(&config.transactions_path);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert!(accepted.is_ok());
+        assert_eq!(
+            Err(
+                "Not enough funds: 21 of 30 is already pending and this transaction needs 10"
+                    .to_string()
+            ),
+            rejected
+        );
+        assert_eq!(1, count);
+    }
+}
@@ -0,0 +1,239 @@
+/*
+    Merkle tree over the transactions in a block
+*/
+
+// 3rd party crates
+use serde_json::Value;
+use sha256::digest;
+
+/// Computes the Merkle root of a list of transactions.
+/// Each transaction JSON is hashed to form the leaves,
+/// then each level is pairwise hashed until one hash
+/// remains. When a level has an odd number of nodes
+/// the last node is paired with itself
+///
+/// An empty list gives the hash of the empty string
+/// and a single transaction gives its leaf hash
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// transactions: &[Value] -> transactions to build the tree from
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+pub fn merkle_root(transactions: &[Value]) -> String {
+    if transactions.is_empty() {
+        return digest("");
+    }
+    let mut level: Vec<String> = transactions.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Builds an inclusion proof for the transaction at
+/// `index`. Each step holds the sibling hash at that
+/// level and whether the sibling sits on the right
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// transactions: &[Value] -> transactions to build the tree from
+/// index: usize           -> position of the transaction to prove
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<(String, bool)>
+/// ```
+pub fn proof(transactions: &[Value], index: usize) -> Vec<(String, bool)> {
+    let mut steps = Vec::new();
+    if index >= transactions.len() {
+        return steps;
+    }
+    let mut level: Vec<String> = transactions.iter().map(hash_leaf).collect();
+    let mut position = index;
+    while level.len() > 1 {
+        let is_left = position.is_multiple_of(2);
+        let sibling = match is_left {
+            true => level.get(position + 1).unwrap_or(&level[position]),
+            false => &level[position - 1],
+        };
+        steps.push((sibling.clone(), is_left));
+        level = next_level(&level);
+        position /= 2;
+    }
+    steps
+}
+
+/// Checks an inclusion proof by hashing the leaf up
+/// through each sibling and comparing against the root
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// leaf: &str               -> leaf hash of the transaction
+/// proof: &[(String, bool)] -> sibling hashes from proof()
+/// root: &str               -> merkle root of the block
+/// ```
+///
+/// # Returns
+/// ```
+/// bool
+/// ```
+pub fn verify_proof(leaf: &str, proof: &[(String, bool)], root: &str) -> bool {
+    let mut hash = leaf.to_string();
+    for (sibling, is_right) in proof {
+        hash = match is_right {
+            true => hash_pair(&hash, sibling),
+            false => hash_pair(sibling, &hash),
+        };
+    }
+    hash == root
+}
+
+/// Hashes a transaction to form a leaf of the tree
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// transaction: &Value -> transaction to hash
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+pub fn hash_leaf(transaction: &Value) -> String {
+    digest(transaction.to_string())
+}
+
+/// Hashes two child nodes to form their parent node
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// left: &str  -> hash of the left child
+/// right: &str -> hash of the right child
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+fn hash_pair(left: &str, right: &str) -> String {
+    let mut values: String = String::from(left);
+    values.push_str(right);
+    digest(values)
+}
+
+/// Builds the level above the given level of nodes,
+/// duplicating the last node if the count is odd
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// level: &[String] -> hashes of the current level
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<String>
+/// ```
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [last] => hash_pair(last, last),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// Testing
+#[cfg(test)]
+mod test_merkle {
+    use super::*;
+
+    use serde_json::json;
+
+    fn transactions(count: i64) -> Vec<Value> {
+        (0..count)
+            .map(|i| json!({ "from_address": "a", "to_address": "b", "amount": i }))
+            .collect()
+    }
+
+    #[test]
+    fn test_merkle_root_empty() {
+        assert_eq!(digest(""), merkle_root(&[]));
+    }
+
+    #[test]
+    fn test_merkle_root_single() {
+        let t = transactions(1);
+
+        assert_eq!(hash_leaf(&t[0]), merkle_root(&t));
+    }
+
+    #[test]
+    fn test_merkle_root_pair() {
+        let t = transactions(2);
+        let expected = hash_pair(&hash_leaf(&t[0]), &hash_leaf(&t[1]));
+
+        assert_eq!(expected, merkle_root(&t));
+    }
+
+    #[test]
+    fn test_merkle_root_odd() {
+        let t = transactions(3);
+        let left = hash_pair(&hash_leaf(&t[0]), &hash_leaf(&t[1]));
+        let right = hash_pair(&hash_leaf(&t[2]), &hash_leaf(&t[2]));
+
+        assert_eq!(hash_pair(&left, &right), merkle_root(&t));
+        assert_ne!(merkle_root(&t[..2]), merkle_root(&t));
+    }
+
+    #[test]
+    fn test_proof() {
+        let t = transactions(4);
+        let root = merkle_root(&t);
+
+        for (i, transaction) in t.iter().enumerate() {
+            let steps = proof(&t, i);
+            assert_eq!(2, steps.len());
+            assert!(verify_proof(&hash_leaf(transaction), &steps, &root));
+        }
+    }
+
+    #[test]
+    fn test_proof_tampered() {
+        let t = transactions(4);
+        let root = merkle_root(&t);
+        let leaf = hash_leaf(&t[1]);
+
+        for i in 0..2 {
+            let mut steps = proof(&t, 1);
+            steps[i].0 = digest("tampered");
+            assert!(!verify_proof(&leaf, &steps, &root));
+        }
+        assert!(!verify_proof(&hash_leaf(&t[2]), &proof(&t, 1), &root));
+    }
+}
@@ -0,0 +1,196 @@
+/*
+    BIP39 style mnemonic backup for private keys
+*/
+
+// 3rd party crates
+use hex::{decode, encode};
+use sha2::{Digest, Sha256};
+
+// imports
+use super::{
+    crypto::KeyPair,
+    log::{Log, LogLevel},
+};
+
+// fixed 2048 word list (BIP39 English)
+static WORD_LIST: &str = include_str!("bip39_english.txt");
+
+/// Encodes a hex private key as a checksummed list of
+/// words. Each word carries 11 bits, the trailing bits
+/// being the first bits of the SHA256 hash of the key
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// private_key: &str -> hex encoded private key
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<String>
+/// ```
+pub fn to_mnemonic(private_key: &str) -> Vec<String> {
+    let entropy = match decode(private_key) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            Log::new_panic(LogLevel::ERROR, 3, Some(vec![private_key.to_string()]));
+            panic!(
+                "Failed to decode private key while creating mnemonic: {}",
+                e
+            );
+        }
+    };
+    let words: Vec<&str> = WORD_LIST.lines().collect();
+
+    let mut bits = to_bits(&entropy);
+    let checksum = to_bits(&Sha256::digest(&entropy));
+    bits.extend_from_slice(&checksum[..entropy.len() / 4]);
+
+    bits.chunks(11)
+        .map(|chunk| {
+            let index = chunk.iter().fold(0, |acc, bit| (acc << 1) | *bit as usize);
+            words[index].to_string()
+        })
+        .collect()
+}
+
+/// Decodes a mnemonic back into a hex private key,
+/// rejecting unknown words and bad checksums
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// words: &[String] -> mnemonic words
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, String>
+/// ```
+pub fn from_mnemonic(words: &[String]) -> Result<String, String> {
+    if ![12, 15, 18, 21, 24].contains(&words.len()) {
+        return Err(format!(
+            "Invalid mnemonic length of {} words, expected 12, 15, 18, 21, or 24",
+            words.len()
+        ));
+    }
+    let list: Vec<&str> = WORD_LIST.lines().collect();
+
+    let mut bits = Vec::new();
+    for word in words {
+        let index = match list.iter().position(|w| w == word) {
+            Some(i) => i,
+            None => return Err(format!("Unknown mnemonic word '{}'", word)),
+        };
+        for shift in (0..11).rev() {
+            bits.push((index >> shift) & 1 == 1);
+        }
+    }
+
+    let checksum_len = bits.len() / 33;
+    let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_len);
+    let entropy: Vec<u8> = entropy_bits
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+        .collect();
+
+    if to_bits(&Sha256::digest(&entropy))[..checksum_len] != *checksum {
+        return Err("Mnemonic checksum does not match, check for mistyped words".to_string());
+    }
+    Ok(encode(entropy))
+}
+
+/// Restores a key pair from a mnemonic created from
+/// its private key
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// name: String     -> name of the account for this key pair
+/// words: &[String] -> mnemonic words
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<KeyPair, String>
+/// ```
+pub fn restore(name: String, words: &[String]) -> Result<KeyPair, String> {
+    let private_key = from_mnemonic(words)?;
+    let seed: [u8; 32] = match decode(&private_key) {
+        Ok(bytes) => match bytes.try_into() {
+            Ok(seed) => seed,
+            Err(_) => return Err("Mnemonic does not encode a 32 byte private key".to_string()),
+        },
+        Err(e) => return Err(e.to_string()),
+    };
+    Ok(KeyPair::from_seed(name, &seed))
+}
+
+/// Splits bytes into bits, most significant first
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// bytes: &[u8] -> bytes to split
+/// ```
+///
+/// # Returns
+/// ```
+/// Vec<bool>
+/// ```
+fn to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1 == 1))
+        .collect()
+}
+
+// Testing
+#[cfg(test)]
+mod test_mnemonic {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let key_pair = KeyPair::generate(String::from("test"));
+
+        let words = to_mnemonic(&key_pair.private_key);
+        let restored = restore(String::from("test"), &words).unwrap();
+
+        assert_eq!(24, words.len());
+        assert_eq!(Ok(key_pair.private_key.clone()), from_mnemonic(&words));
+        assert_eq!(key_pair.private_key, restored.private_key);
+        assert_eq!(key_pair.public_key, restored.public_key);
+    }
+
+    #[test]
+    fn test_known_vector() {
+        // BIP39 test vector for 32 bytes of 0x00 entropy
+        let mut expected = vec!["abandon".to_string(); 23];
+        expected.push("art".to_string());
+
+        assert_eq!(expected, to_mnemonic(&"00".repeat(32)));
+    }
+
+    #[test]
+    fn test_corrupted_mnemonic() {
+        let mut words = to_mnemonic(&"00".repeat(32));
+        words[0] = "ability".to_string();
+        let mut mistyped = to_mnemonic(&"00".repeat(32));
+        mistyped[3] = "abandan".to_string();
+
+        assert!(from_mnemonic(&words).is_err());
+        assert_eq!(
+            Err("Unknown mnemonic word 'abandan'".to_string()),
+            from_mnemonic(&mistyped)
+        );
+    }
+}
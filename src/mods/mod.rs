@@ -1,11 +1,26 @@
+pub mod amount;
 pub mod base;
 pub mod block;
+pub mod blockchain;
+pub mod cli;
+pub mod config;
 pub mod crypto;
+pub mod events;
 pub mod file;
 pub mod helpers;
+pub mod ledger;
 pub mod log;
+pub mod mempool;
+pub mod merkle;
 pub mod messaging;
+pub mod mnemonic;
+pub mod p2p;
 pub mod repl;
+#[cfg(test)]
+mod roundtrip;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod signing_data;
+pub mod storage;
 pub mod transaction;
 pub mod wallet;
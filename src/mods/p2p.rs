@@ -0,0 +1,483 @@
+/*
+    Peer to peer block broadcast between nodes on a
+    mock network. Every message is a JSON document
+    prefixed with its length as a big-endian u32
+*/
+
+// std library
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+// 3rd party crates
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, json, to_vec, Value};
+
+// imports
+use super::{
+    base::Blockchain,
+    block::{Block, DIFFICULTY_WINDOW, MIN_DIFFICULTY, TARGET_BLOCK_TIME_SECS},
+    config::Config,
+    events::{Event, LedgerEvents},
+    file::FileOps,
+};
+
+/// Largest message a node will accept
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Defines what a node can send to its peers
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// Block -> a newly mined block to append to the tip
+/// Chain -> a full chain to apply the longest chain rule to
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug
+/// ```
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    Block(Block),
+    Chain(Vec<Block>),
+}
+
+/// Defines a node listening for blocks from its peers
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// addr: SocketAddr        -> address this node listens on
+/// peers: Vec<SocketAddr>  -> nodes to broadcast to
+/// ```
+///
+/// # Derives
+/// ```
+/// Clone, Debug
+/// ```
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub addr: SocketAddr,
+    pub peers: Vec<SocketAddr>,
+}
+
+impl Node {
+    /// Starts a node on `addr` in a background thread,
+    /// applying received blocks to the chain in `config`.
+    /// Binding to port 0 picks a free port
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// addr: &str              -> address to bind e.g. "127.0.0.1:9000"
+    /// config: &Config         -> locations of the data files
+    /// peers: Vec<SocketAddr>  -> nodes to broadcast to
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// io::Result<(Node, JoinHandle<()>)>
+    /// ```
+    pub fn start(
+        addr: &str,
+        config: &Config,
+        peers: Vec<SocketAddr>,
+    ) -> io::Result<(Node, JoinHandle<()>)> {
+        let listener = TcpListener::bind(addr)?;
+        let node = Node {
+            addr: listener.local_addr()?,
+            peers,
+        };
+        let config = config.clone();
+        let handle = thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                // a peer that drops the connection is not fatal
+                let _ = Node::receive(&mut stream, &config);
+            }
+        });
+        Ok((node, handle))
+    }
+
+    /// Sends a block to every known peer and waits for
+    /// each to reply
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// block: &Block -> block to send
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize -> number of peers that accepted the block
+    /// ```
+    pub fn broadcast(&self, block: &Block) -> usize {
+        self.send_all(&Payload::Block(block.clone()))
+    }
+
+    /// Sends a full chain to every known peer so nodes
+    /// that have fallen behind can catch up
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// chain: &[Block] -> chain to send
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize -> number of peers that replaced their chain
+    /// ```
+    pub fn broadcast_chain(&self, chain: &[Block]) -> usize {
+        self.send_all(&Payload::Chain(chain.to_vec()))
+    }
+
+    /// Registers a LedgerEvents callback that passes each
+    /// block added to the chain in `config` on to every
+    /// peer. A peer that already has the block rejects it
+    /// so relayed blocks do not loop. Sending happens on
+    /// its own thread so a peer relaying back to this node
+    /// cannot block its listener
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// config: &Config -> locations of the data files
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize -> id to pass to LedgerEvents::unregister
+    /// ```
+    pub fn relay_blocks(&self, config: &Config) -> usize {
+        let node = self.clone();
+        let blockchain_path = config.blockchain_path.clone();
+        LedgerEvents::register(move |event| {
+            if let Event::BlockAdded { path, hash, .. } = event {
+                if *path != blockchain_path {
+                    return;
+                }
+                let node = node.clone();
+                let (path, hash) = (path.clone(), hash.clone());
+                thread::spawn(move || {
+                    if let Some(block) = Blockchain::get_block_by_hash(&path, &hash) {
+                        node.broadcast(&block);
+                    }
+                });
+            }
+        })
+    }
+
+    /// Sends a payload to every peer, counting the ones
+    /// that accepted it
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// payload: &Payload -> message to send
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// usize
+    /// ```
+    fn send_all(&self, payload: &Payload) -> usize {
+        let message = match serde_json::to_value(payload) {
+            Ok(value) => value,
+            Err(_) => return 0,
+        };
+        self.peers
+            .iter()
+            .filter(|peer| Node::send(peer, &message).unwrap_or(false))
+            .count()
+    }
+
+    /// Sends one message to a peer and reads its reply
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// peer: &SocketAddr -> address of the peer
+    /// message: &Value   -> message to send
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// io::Result<bool> -> whether the peer accepted the message
+    /// ```
+    fn send(peer: &SocketAddr, message: &Value) -> io::Result<bool> {
+        let mut stream = TcpStream::connect(peer)?;
+        write_frame(&mut stream, message)?;
+        let reply = read_frame(&mut stream)?;
+        Ok(reply["accepted"] == true)
+    }
+
+    /// Reads one message from a peer, applies it to the
+    /// local chain and replies with the outcome
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// stream: &mut TcpStream -> connection from the peer
+    /// config: &Config        -> locations of the data files
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// io::Result<()>
+    /// ```
+    fn receive(stream: &mut TcpStream, config: &Config) -> io::Result<()> {
+        let message = read_frame(stream)?;
+        // blocks are read one at a time so each is checked
+        // against its own contents before anything else
+        let algo = match Blockchain::hash_algo(&config.blockchain_path) {
+            Ok(algo) => algo,
+            Err(e) => return write_frame(stream, &json!({ "accepted": false, "error": e })),
+        };
+        let outcome = match (message.get("block"), message["chain"].as_array()) {
+            (Some(block), _) => {
+                Block::from_value_with(block, algo).and_then(|block| extend(config, block))
+            }
+            (_, Some(chain)) => chain
+                .iter()
+                .enumerate()
+                .map(|(i, block)| Block::from_chain_value(block, i, algo))
+                .collect::<Result<Vec<Block>, String>>()
+                .and_then(|chain| {
+                    Blockchain::replace_chain(&config.blockchain_path, chain, MIN_DIFFICULTY)
+                }),
+            _ => Err("Unknown message, expected a block or chain".to_string()),
+        };
+        let reply = match outcome {
+            Ok(accepted) => json!({ "accepted": accepted }),
+            Err(e) => json!({ "accepted": false, "error": e }),
+        };
+        write_frame(stream, &reply)
+    }
+}
+
+/// Appends a block from a peer if it was mined on top
+/// of the local tip. add_block would otherwise re-link
+/// it to whatever the tip is
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// block: Block    -> block received from a peer
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<bool, String>
+/// ```
+fn extend(config: &Config, block: Block) -> Result<bool, String> {
+    let base_data = FileOps::parse_validated(&config.blockchain_path, "blockchain")?;
+    let tip = base_data["blockchain"]
+        .as_array()
+        .and_then(|chain| chain.last())
+        .and_then(|tip| tip["hash"].as_str());
+    if tip != Some(block.previous_hash.as_str()) {
+        return Err("Block does not extend the local chain".to_string());
+    }
+    let difficulty = Blockchain::adjust_difficulty(
+        &config.blockchain_path,
+        TARGET_BLOCK_TIME_SECS,
+        DIFFICULTY_WINDOW,
+    );
+    Blockchain::add_block(&config.blockchain_path, block, difficulty).map(|_| true)
+}
+
+/// Writes a length-prefixed JSON message
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// stream: &mut TcpStream -> connection to write to
+/// value: &Value          -> message to write
+/// ```
+///
+/// # Returns
+/// ```
+/// io::Result<()>
+/// ```
+fn write_frame(stream: &mut TcpStream, value: &Value) -> io::Result<()> {
+    let body = to_vec(value)?;
+    let len = match u32::try_from(body.len()) {
+        Ok(len) => len,
+        Err(_) => return Err(io::Error::other("Message too large to send")),
+    };
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Reads a length-prefixed JSON message
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// stream: &mut TcpStream -> connection to read from
+/// ```
+///
+/// # Returns
+/// ```
+/// io::Result<Value>
+/// ```
+fn read_frame(stream: &mut TcpStream) -> io::Result<Value> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::other("Message exceeds the maximum frame size"));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok(from_slice(&body)?)
+}
+
+// Testing
+#[cfg(test)]
+mod test_p2p {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+    use std::time::Duration;
+
+    use crate::mods::{block::DIFFICULTY, crypto::HashAlgo};
+
+    #[test]
+    fn test_broadcast() {
+        let first = Config::new(&temp_dir().join("mockchain_test_p2p_first"));
+        let second = Config::new(&temp_dir().join("mockchain_test_p2p_second"));
+        FileOps::init(&first, false);
+        FileOps::init(&second, false);
+
+        let (receiver, _) = Node::start("127.0.0.1:0", &second, vec![]).unwrap();
+        let (sender, _) = Node::start("127.0.0.1:0", &first, vec![receiver.addr]).unwrap();
+
+        Blockchain::add_block(
+            &first.blockchain_path,
+            Block::new(String::new(), vec![]),
+            DIFFICULTY,
+        )
+        .unwrap();
+        let mined = Blockchain::get_block_by_index(&first.blockchain_path, 1).unwrap();
+        let mut tampered = mined.clone();
+        tampered.merkle_root = "f".repeat(64);
+        let forged = sender.broadcast(&tampered);
+        let accepted = sender.broadcast(&mined);
+        let received = Blockchain::get_block_by_hash(&second.blockchain_path, &mined.hash);
+        let valid = Blockchain::validate_chain(&second.blockchain_path, DIFFICULTY);
+        // the same block again no longer links to the tip
+        let duplicate = sender.broadcast(&mined);
+        fs::remove_dir_all(&first.data_dir).unwrap();
+        fs::remove_dir_all(&second.data_dir).unwrap();
+
+        assert_eq!(0, forged);
+        assert_eq!(1, accepted);
+        assert!(received.is_some());
+        assert_eq!(Ok(()), valid);
+        assert_eq!(0, duplicate);
+    }
+
+    #[test]
+    fn test_broadcast_chain_hash_algo() {
+        let first = Config::new(&temp_dir().join("mockchain_test_p2p_algo_first"));
+        let second = Config::new(&temp_dir().join("mockchain_test_p2p_algo_second"));
+        for config in [&first, &second] {
+            FileOps::init(config, false);
+            Blockchain::set_hash_algo(&config.blockchain_path, HashAlgo::Blake3).unwrap();
+        }
+
+        let (receiver, _) = Node::start("127.0.0.1:0", &second, vec![]).unwrap();
+        let (sender, _) = Node::start("127.0.0.1:0", &first, vec![receiver.addr]).unwrap();
+        Blockchain::add_block(
+            &first.blockchain_path,
+            Block::new(String::new(), vec![]),
+            DIFFICULTY,
+        )
+        .unwrap();
+        let chain: Vec<Block> = (0..2)
+            .filter_map(|i| Blockchain::get_block_by_index(&first.blockchain_path, i))
+            .collect();
+        // the SHA-256 genesis block must not be checked with BLAKE3
+        let accepted = sender.broadcast_chain(&chain);
+        let received = Blockchain::get_block_by_index(&second.blockchain_path, 1);
+        fs::remove_dir_all(&first.data_dir).unwrap();
+        fs::remove_dir_all(&second.data_dir).unwrap();
+
+        assert_eq!(2, chain.len());
+        assert_eq!(1, accepted);
+        assert_eq!(chain.last(), received.as_ref());
+    }
+
+    #[test]
+    fn test_relay_blocks() {
+        let first = Config::new(&temp_dir().join("mockchain_test_p2p_relay_first"));
+        let second = Config::new(&temp_dir().join("mockchain_test_p2p_relay_second"));
+        let third = Config::new(&temp_dir().join("mockchain_test_p2p_relay_third"));
+        for config in [&first, &second, &third] {
+            FileOps::init(config, false);
+        }
+
+        let (last, _) = Node::start("127.0.0.1:0", &third, vec![]).unwrap();
+        let (middle, _) = Node::start("127.0.0.1:0", &second, vec![last.addr]).unwrap();
+        let (sender, _) = Node::start("127.0.0.1:0", &first, vec![middle.addr]).unwrap();
+        let id = middle.relay_blocks(&second);
+
+        Blockchain::add_block(
+            &first.blockchain_path,
+            Block::new(String::new(), vec![]),
+            DIFFICULTY,
+        )
+        .unwrap();
+        let mined = Blockchain::get_block_by_index(&first.blockchain_path, 1).unwrap();
+        let accepted = sender.broadcast(&mined);
+        // the relay runs on its own thread so give it time to arrive
+        let mut relayed = None;
+        for _ in 0..50 {
+            relayed = Blockchain::get_block_by_hash(&third.blockchain_path, &mined.hash);
+            if relayed.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        let removed = LedgerEvents::unregister(id);
+        for config in [&first, &second, &third] {
+            fs::remove_dir_all(&config.data_dir).unwrap();
+        }
+
+        assert_eq!(1, accepted);
+        assert_eq!(Some(mined), relayed);
+        assert!(removed);
+    }
+}
@@ -97,15 +97,6 @@ impl Repl {
     where
         <T as FromStr>::Err: Debug,
     {
-        let i: Option<T> = match try_read!() {
-            Ok(val) => Some(val),
-            Err(_) => None,
-        };
-
-        if i.is_none() {
-            None
-        } else {
-            i
-        }
+        try_read!().ok()
     }
 }
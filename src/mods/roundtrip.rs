@@ -0,0 +1,118 @@
+/*
+    Serde round trips for the structs written to the
+    data files, so a renamed field or changed type is
+    caught before it reaches a user's files
+*/
+
+// Testing
+#[cfg(test)]
+mod test_roundtrip {
+    use std::fmt::Debug;
+
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::{from_str, from_value, json, to_string};
+
+    use crate::mods::{
+        amount::Amount,
+        block::Block,
+        crypto::KeyPair,
+        signing_data::Signing,
+        transaction::{Output, Transaction},
+        wallet::Wallet,
+    };
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+        let json = to_string(&value).unwrap();
+        let decoded: T = from_str(&json).unwrap();
+        assert_eq!(value, decoded);
+    }
+
+    #[test]
+    fn test_wallet() {
+        round_trip(Wallet {
+            name: String::from("alice"),
+            address: "0".repeat(130),
+            balance: -5,
+            frozen: true,
+        });
+    }
+
+    #[test]
+    fn test_block() {
+        round_trip(Block::genesis());
+        let mut block = Block::new(Block::genesis().hash, vec![json!({ "amount": 5 })]);
+        block.add_coinbase("miner", 50);
+        block.mine(1);
+        round_trip(block);
+    }
+
+    #[test]
+    fn test_key_pair() {
+        round_trip(KeyPair::generate(String::from("alice")));
+    }
+
+    #[test]
+    fn test_signing() {
+        round_trip(Signing {
+            name: String::from("alice"),
+            hash: "a".repeat(64),
+            signing_key: "b".repeat(130),
+            signature: "c".repeat(128),
+        });
+    }
+
+    fn legacy() -> Transaction {
+        Transaction {
+            hash: "a".repeat(64),
+            txid: String::new(),
+            from_address: String::from("a"),
+            to_address: String::from("b"),
+            amount: Amount::new(10).unwrap(),
+            outputs: Vec::new(),
+            fee: 2,
+            timestamp: String::new(),
+            signature: "c".repeat(128),
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        round_trip(legacy());
+        round_trip(Transaction {
+            txid: "d".repeat(64),
+            timestamp: String::from("2024-06-01T00:00:00+00:00"),
+            outputs: vec![
+                Output {
+                    to: String::from("b"),
+                    amount: 4,
+                },
+                Output {
+                    to: String::from("c"),
+                    amount: 6,
+                },
+            ],
+            ..legacy()
+        });
+    }
+
+    #[test]
+    fn test_missing_optional_fields() {
+        let wallet: Wallet =
+            from_value(json!({ "name": "alice", "address": "a", "balance": 20 })).unwrap();
+        let transaction: Transaction = from_value(json!({
+            "hash": "h",
+            "from_address": "a",
+            "to_address": "b",
+            "amount": 10,
+            "signature": "s",
+        }))
+        .unwrap();
+
+        assert!(!wallet.frozen);
+        assert_eq!(20, wallet.balance);
+        assert!(transaction.txid.is_empty());
+        assert!(transaction.outputs.is_empty());
+        assert_eq!(0, transaction.fee);
+        assert!(transaction.timestamp.is_empty());
+    }
+}
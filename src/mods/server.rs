@@ -0,0 +1,303 @@
+/*
+    JSON REST API over the data files, enabled with
+    the "server" feature
+*/
+
+// std library
+use std::io;
+use std::net::SocketAddr;
+use std::thread::{self, JoinHandle};
+
+// 3rd party crates
+use serde_json::{from_str, json, to_value, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// imports
+use super::{
+    base::Blockchain, config::Config, file::FileOps, mempool, transaction::Transaction,
+    wallet::Wallet,
+};
+
+/// Starts the API server on `addr` in a background
+/// thread, serving the data files in `config`.
+/// Binding to port 0 picks a free port, so the bound
+/// address is returned with the thread handle
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// addr: &str      -> address to bind e.g. "127.0.0.1:8080"
+/// config: &Config -> locations of the data files
+/// ```
+///
+/// # Returns
+/// ```
+/// io::Result<(SocketAddr, JoinHandle<()>)>
+/// ```
+pub fn spawn(addr: &str, config: &Config) -> io::Result<(SocketAddr, JoinHandle<()>)> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    let bound = match server.server_addr().to_ip() {
+        Some(ip) => ip,
+        None => return Err(io::Error::other("Server is not listening on an IP address")),
+    };
+    let config = config.clone();
+    let handle = thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(request, &config);
+        }
+    });
+    Ok((bound, handle))
+}
+
+/// Routes a request to its endpoint and sends the
+/// JSON response
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// request: Request -> incoming HTTP request
+/// config: &Config  -> locations of the data files
+/// ```
+///
+/// # Returns
+/// Nothing
+fn handle(mut request: Request, config: &Config) {
+    let segments: Vec<String> = request
+        .url()
+        .trim_matches('/')
+        .split('/')
+        .map(String::from)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    let (status, body) = match (request.method(), segments.as_slice()) {
+        (Method::Get, ["wallets", name, "balance"]) => balance(config, name),
+        (Method::Get, ["blocks", index]) => block(config, index),
+        (Method::Post, ["transactions"]) => {
+            let mut content = String::new();
+            match request.as_reader().read_to_string(&mut content) {
+                Ok(_) => submit(config, &content),
+                Err(e) => (400, error(e.to_string())),
+            }
+        }
+        _ => (404, error(String::from("Not found"))),
+    };
+
+    let header =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    // the client may have gone away, nothing to recover
+    let _ = request.respond(response);
+}
+
+/// GET /wallets/{name}/balance
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// name: &str      -> name of the account
+/// ```
+///
+/// # Returns
+/// ```
+/// (u16, Value) -> status code and response body
+/// ```
+fn balance(config: &Config, name: &str) -> (u16, Value) {
+    match Wallet::get_balance(&FileOps {}, &config.wallets_path, name) {
+        Ok(Some(balance)) => (200, json!({ "name": name, "balance": balance })),
+        Ok(None) => (404, error(format!("No wallet found under name '{}'", name))),
+        Err(e) => (500, error(e.to_string())),
+    }
+}
+
+/// GET /blocks/{index}
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// index: &str     -> position of the block in the chain
+/// ```
+///
+/// # Returns
+/// ```
+/// (u16, Value) -> status code and response body
+/// ```
+fn block(config: &Config, index: &str) -> (u16, Value) {
+    let index: usize = match index.parse() {
+        Ok(i) => i,
+        Err(_) => return (400, error(format!("Invalid block index '{}'", index))),
+    };
+    match Blockchain::get_block_by_index(&config.blockchain_path, index) {
+        Some(block) => match to_value(block) {
+            Ok(value) => (200, value),
+            Err(e) => (500, error(e.to_string())),
+        },
+        None => (404, error(format!("No block found at index {}", index))),
+    }
+}
+
+/// POST /transactions
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// config: &Config -> locations of the data files
+/// content: &str   -> request body holding the transaction JSON
+/// ```
+///
+/// # Returns
+/// ```
+/// (u16, Value) -> status code and response body
+/// ```
+fn submit(config: &Config, content: &str) -> (u16, Value) {
+    let tx: Value = match from_str(content) {
+        Ok(tx) => tx,
+        Err(e) => return (400, error(e.to_string())),
+    };
+    let hash = tx["hash"].clone();
+    let txid = Transaction::txid(&tx);
+    match mempool::add(config, tx) {
+        Ok(_) => (201, json!({ "hash": hash, "txid": txid })),
+        Err(e) => (400, error(e)),
+    }
+}
+
+/// Builds an error response body
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// message: String -> description of the error
+/// ```
+///
+/// # Returns
+/// ```
+/// Value
+/// ```
+fn error(message: String) -> Value {
+    json!({ "error": message })
+}
+
+// Testing
+#[cfg(test)]
+mod test_server {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    use crate::mods::crypto::{hash_transaction, KeyPair};
+
+    fn send(addr: SocketAddr, method: &str, url: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            url,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("");
+        (status, from_str(body).unwrap_or(Value::Null))
+    }
+
+    fn signed(sender: &KeyPair, signer: &KeyPair, to: &str, amount: i64) -> Value {
+        let hash = hash_transaction(&sender.public_key, to, &amount.to_string());
+        let mut tx = json!({
+            "hash": hash,
+            "from_address": sender.public_key,
+            "to_address": to,
+            "amount": amount,
+            "fee": 0,
+        });
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            signer.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+        tx
+    }
+
+    #[test]
+    fn test_server() {
+        let config = Config::new(&temp_dir().join("mockchain_test_server"));
+        FileOps::init(&config, false);
+        let alice = Wallet::new(
+            &config.wallets_path,
+            &config.keypairs_path,
+            String::from("alice"),
+        )
+        .unwrap();
+        let bob = Wallet::new(
+            &config.wallets_path,
+            &config.keypairs_path,
+            String::from("bob"),
+        )
+        .unwrap();
+        FileOps::write_balance(
+            &config.wallets_path,
+            FileOps::parse(&config.wallets_path).unwrap(),
+            alice.address.clone(),
+            30,
+        )
+        .unwrap();
+        let keypairs = FileOps::parse(&config.keypairs_path).unwrap();
+        let alice_keys = KeyPair {
+            name: String::from("alice"),
+            public_key: alice.address.clone(),
+            private_key: keypairs["keypairs"][0]["private_key"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        };
+        let mallory = KeyPair::generate(String::from("mallory"));
+
+        let (addr, _) = spawn("127.0.0.1:0", &config).unwrap();
+        let balance = send(addr, "GET", "/wallets/alice/balance", "");
+        let missing_wallet = send(addr, "GET", "/wallets/carol/balance", "");
+        let genesis = send(addr, "GET", "/blocks/0", "");
+        let missing_block = send(addr, "GET", "/blocks/5", "");
+        let valid = signed(&alice_keys, &alice_keys, &bob.address, 10).to_string();
+        let accepted = send(addr, "POST", "/transactions", &valid);
+        let forged = signed(&alice_keys, &mallory, &bob.address, 20).to_string();
+        let rejected = send(addr, "POST", "/transactions", &forged);
+        let unknown = signed(&alice_keys, &alice_keys, "carol", 5).to_string();
+        let unknown_recipient = send(addr, "POST", "/transactions", &unknown);
+        let pending = mempool::pending_count(&config.transactions_path);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!((200, json!({ "name": "alice", "balance": 30 })), balance);
+        assert_eq!(404, missing_wallet.0);
+        assert_eq!(200, genesis.0);
+        assert_eq!("0".repeat(64), genesis.1["previous_hash"]);
+        assert_eq!(404, missing_block.0);
+        assert_eq!(201, accepted.0);
+        assert_eq!(400, rejected.0);
+        assert_eq!(400, unknown_recipient.0);
+        assert_eq!(1, pending);
+    }
+}
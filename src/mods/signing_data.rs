@@ -1,5 +1,5 @@
 // 3rd party crates
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Define a Signing object
 ///
@@ -16,9 +16,9 @@ use serde::Serialize;
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Signing {
     pub name: String,
     pub hash: String,
@@ -0,0 +1,140 @@
+/*
+    Storage backends for the JSON data files
+*/
+
+// std library
+#[cfg(test)]
+use std::cell::RefCell;
+#[cfg(test)]
+use std::collections::HashMap;
+use std::path::Path;
+#[cfg(test)]
+use std::path::PathBuf;
+
+// 3rd party crates
+use serde_json::Value;
+
+// imports
+use super::file::{FileOps, FileOpsError};
+
+/// Reads and writes whole JSON documents by key. The
+/// key is the data file path so the same call sites
+/// work against the file system or memory
+///
+/// # Visibility
+/// public
+pub trait Storage {
+    /// Reads the document stored under `key`
+    ///
+    /// # Args
+    /// ```
+    /// key: &Path -> data file path
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, FileOpsError>
+    /// ```
+    fn read(&self, key: &Path) -> Result<Value, FileOpsError>;
+
+    /// Replaces the document stored under `key`
+    ///
+    /// # Args
+    /// ```
+    /// key: &Path    -> data file path
+    /// value: &Value -> document to store
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), FileOpsError>
+    /// ```
+    fn write(&self, key: &Path, value: &Value) -> Result<(), FileOpsError>;
+}
+
+impl Storage for FileOps {
+    fn read(&self, key: &Path) -> Result<Value, FileOpsError> {
+        FileOps::parse(key)
+    }
+
+    fn write(&self, key: &Path, value: &Value) -> Result<(), FileOpsError> {
+        FileOps::overwrite(key, value)
+    }
+}
+
+/// In-memory storage backed by a HashMap so tests do
+/// not need real data files
+///
+/// # Visibility
+/// public, test builds only
+///
+/// # Fields
+/// ```
+/// documents: RefCell<HashMap<PathBuf, Value>> -> stored documents
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Default
+/// ```
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemStorage {
+    documents: RefCell<HashMap<PathBuf, Value>>,
+}
+
+#[cfg(test)]
+impl MemStorage {
+    /// Creates an empty MemStorage
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// MemStorage
+    /// ```
+    pub fn new() -> MemStorage {
+        MemStorage::default()
+    }
+}
+
+#[cfg(test)]
+impl Storage for MemStorage {
+    fn read(&self, key: &Path) -> Result<Value, FileOpsError> {
+        match self.documents.borrow().get(key) {
+            Some(value) => Ok(value.clone()),
+            None => Err(FileOpsError::NotFound(key.to_path_buf())),
+        }
+    }
+
+    fn write(&self, key: &Path, value: &Value) -> Result<(), FileOpsError> {
+        self.documents
+            .borrow_mut()
+            .insert(key.to_path_buf(), value.clone());
+        Ok(())
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_storage {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn test_mem_storage() {
+        let storage = MemStorage::new();
+        let key = Path::new("wallets.json");
+
+        let missing = storage.read(key);
+        storage.write(key, &json!({ "wallets": [] })).unwrap();
+
+        assert!(matches!(missing, Err(FileOpsError::NotFound(_))));
+        assert_eq!(json!({ "wallets": [] }), storage.read(key).unwrap());
+    }
+}
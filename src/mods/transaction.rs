@@ -1,21 +1,24 @@
 // std library
-use std::fs;
+use std::path::Path;
 
 // 3rd party crates
-use serde::Serialize;
-use serde_json::to_string;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, Value};
+use sha256::digest;
 
 // imports
 use super::{
-    base::Transactions,
+    amount::Amount,
     crypto::{hash_transaction, KeyPair},
     file::FileOps,
-    log::{Log, LogLevel},
-    signing_data::Signing,
+    wallet::Wallet,
 };
-use crate::{SIGNING_DATA_PATH, TRANSACTIONS_PATH};
 
-/// Define a Transaction object
+/// Define a Transaction object. A transaction paying
+/// several recipients lists them in `outputs`, which then
+/// takes the place of `to_address` and `amount`. The
+/// creation `timestamp` tells apart repeat payments of
+/// the same amount to the same recipient
 ///
 /// # Visibility
 /// public
@@ -23,94 +26,635 @@ use crate::{SIGNING_DATA_PATH, TRANSACTIONS_PATH};
 /// # Fields
 /// ```
 /// hash: String,
+/// txid: String,
 /// from_address: String,
 /// to_address: String,
-/// amount: i32,
+/// amount: Amount,
+/// outputs: Vec<Output>,
+/// fee: u32,
+/// timestamp: String,
 /// signature: String
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Transaction {
     pub hash: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub txid: String,
     pub from_address: String,
     pub to_address: String,
-    pub amount: i32,
+    pub amount: Amount,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<Output>,
+    #[serde(default)]
+    pub fee: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub timestamp: String,
     pub signature: String,
 }
 
+/// Defines one payment made by a transaction
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// to: String  -> address of the recipient
+/// amount: i64 -> amount paid to the recipient
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Output {
+    pub to: String,
+    pub amount: i64,
+}
+
 impl Transaction {
-    /// Generates a reward Transaction after a Block has
-    /// been mined. Once the current transactions have been
-    /// cleared this transaction is added as the first in
-    /// a new list of transactions
+    /// Checks a transaction was signed by its sender. The
+    /// sender must have a wallet, the hash must match the
+    /// sender and outputs, and the signature over the
+    /// canonical bytes must verify against the senders
+    /// public key
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: String -> name of miner of last Block
+    /// tx: &Value          -> transaction JSON to check
+    /// wallets_path: &Path -> path to wallets.json
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn add_reward(name: String) {
-        let to_address = KeyPair::get_key(name.clone(), String::from("public"));
-        let hash = hash_transaction(&String::from("REWARD"), &to_address, &String::from("50"));
-
-        let private_key = KeyPair::get_key(name.clone(), String::from("private"));
-        let (signature, signing_key) = KeyPair::sign(&hash, private_key);
-
-        let signing_data = Signing {
-            name: name.clone(),
-            hash: hash.clone(),
-            signing_key,
-            signature: signature.clone(),
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn verify(tx: &Value, wallets_path: &Path) -> Result<(), String> {
+        let (from, signature) = match (tx["from_address"].as_str(), tx["signature"].as_str()) {
+            (Some(from), Some(signature)) => (from, signature),
+            _ => return Err("Transaction is missing an address or signature".to_string()),
         };
+        Transaction::outputs(tx)?;
+        let wallets = Wallet::list_all(&FileOps {}, wallets_path).map_err(|e| e.to_string())?;
+        if !wallets.iter().any(|w| w.address == from) {
+            return Err(format!("No wallet found with address {}", from));
+        }
+        if tx["hash"] != Transaction::content_hash(tx) {
+            return Err("Transaction hash does not match its contents".to_string());
+        }
+        if !KeyPair::verify_with_public_key(signature, from, &Transaction::canonical_bytes(tx)) {
+            return Err(format!("Signature could not be verified for {}", from));
+        }
+        Ok(())
+    }
 
-        let reward = Transaction {
-            hash,
-            from_address: String::from("REWARD"),
-            to_address,
-            amount: 50,
-            signature,
+    /// Builds the bytes a transaction signature covers.
+    /// The sender, recipient, amount, fee and timestamp
+    /// are joined in a fixed order so the signature does
+    /// not depend on how the JSON keys are ordered. With
+    /// `outputs` every recipient and amount is covered in
+    /// the listed order. Transactions made before the
+    /// timestamp was added are covered without it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value -> transaction JSON
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<u8>
+    /// ```
+    pub fn canonical_bytes(tx: &Value) -> Vec<u8> {
+        let from = tx["from_address"].as_str().unwrap_or("");
+        let fee = tx["fee"].as_u64().unwrap_or(0);
+        let bytes = match tx.get("outputs") {
+            Some(_) => format!(
+                "{}:{}:{}",
+                from,
+                Transaction::joined(&Transaction::outputs(tx).unwrap_or_default()),
+                fee
+            ),
+            None => format!(
+                "{}:{}:{}:{}",
+                from,
+                tx["to_address"].as_str().unwrap_or(""),
+                tx["amount"],
+                fee
+            ),
         };
+        match tx["timestamp"].as_str() {
+            Some(timestamp) if !timestamp.is_empty() => format!("{}:{}", bytes, timestamp),
+            _ => bytes,
+        }
+        .into_bytes()
+    }
 
-        FileOps::write(TRANSACTIONS_PATH.as_path(), "transactions", reward);
-        FileOps::write(SIGNING_DATA_PATH.as_path(), "signing_data", signing_data);
+    /// Works out the value of a transaction's hash field.
+    /// A single payment hashes its sender, recipient and
+    /// amount, a multi-output transaction hashes its
+    /// sender, every output and the total paid
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value -> transaction JSON
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    pub fn content_hash(tx: &Value) -> String {
+        let from = tx["from_address"].to_string().replace("\"", "");
+        match tx.get("outputs") {
+            Some(_) => {
+                let outputs = Transaction::outputs(tx).unwrap_or_default();
+                let total: i64 = outputs.iter().map(|o| o.amount).sum();
+                hash_transaction(&from, &Transaction::joined(&outputs), &total.to_string())
+            }
+            None => hash_transaction(
+                &from,
+                &tx["to_address"].to_string().replace("\"", ""),
+                &tx["amount"].to_string(),
+            ),
+        }
     }
 
-    /// Clears all current transactions after mining
+    /// Gets the payments made by a transaction, reading a
+    /// single `to_address` and `amount` as one output.
+    /// Negative amounts, or outputs whose total would
+    /// overflow, are rejected
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
-    /// None
+    /// ```
+    /// tx: &Value -> transaction JSON
+    /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn clear() {
-        let t = match to_string(&Transactions { transactions: [] }) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 14, None);
-                panic!(
-                    "Failed to parse transactions to String before clearing: {}",
-                    e
-                );
+    /// ```
+    /// Result<Vec<Output>, String>
+    /// ```
+    pub fn outputs(tx: &Value) -> Result<Vec<Output>, String> {
+        if let Some(outputs) = tx.get("outputs") {
+            let outputs: Vec<Output> = from_value(outputs.clone())
+                .map_err(|e| format!("Invalid transaction outputs: {}", e))?;
+            if outputs.is_empty() {
+                return Err("Transaction has no outputs".to_string());
             }
-        };
-        match fs::write(TRANSACTIONS_PATH.as_path(), t) {
-            Ok(_) => {}
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 9, Some(vec!["transactions".to_string()]));
-                panic!("Failed to write transactions.json: {}", e);
+            let mut total = Amount::default();
+            for output in &outputs {
+                total = total.checked_add(Amount::new(output.amount)?)?;
             }
+            return Ok(outputs);
+        }
+        match (tx["to_address"].as_str(), tx["amount"].as_i64()) {
+            (Some(to), Some(amount)) => Ok(vec![Output {
+                to: to.to_string(),
+                amount: Amount::new(amount)?.value(),
+            }]),
+            _ => Err("Transaction is missing a recipient or amount".to_string()),
+        }
+    }
+
+    /// Gets what a transaction takes from its sender, the
+    /// sum of its outputs plus the fee
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value -> transaction JSON
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i64 -> 0 is counted for unreadable outputs and
+    ///        i64::MAX if the total overflows
+    /// ```
+    pub fn cost(tx: &Value) -> i64 {
+        let paid: i64 = Transaction::outputs(tx)
+            .unwrap_or_default()
+            .iter()
+            .map(|o| o.amount)
+            .sum();
+        paid.saturating_add(tx["fee"].as_i64().unwrap_or(0))
+    }
+
+    /// Joins outputs as "to=amount" pairs in order
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// outputs: &[Output] -> outputs to join
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    fn joined(outputs: &[Output]) -> String {
+        outputs
+            .iter()
+            .map(|o| format!("{}={}", o.to, o.amount))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Gets the unique id of a transaction, the hash of
+    /// its canonical bytes. Unlike the hash field this
+    /// covers the fee and timestamp as well
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value -> transaction JSON
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    pub fn txid(tx: &Value) -> String {
+        digest(Transaction::canonical_bytes(tx))
+    }
+
+    /// Checks the sender can afford a transaction once
+    /// the amounts and fees of their transactions already
+    /// waiting in the mempool are taken off their balance,
+    /// so pending transactions can never overdraw a wallet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// tx: &Value           -> transaction JSON to check
+    /// wallets_path: &Path  -> path to wallets.json
+    /// mempool_path: &Path  -> path to transactions.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn check_spendable(
+        tx: &Value,
+        wallets_path: &Path,
+        mempool_path: &Path,
+    ) -> Result<(), String> {
+        let from = match tx["from_address"].as_str() {
+            Some(from) => from,
+            None => return Err("Transaction is missing a sender address".to_string()),
+        };
+        if Transaction::outputs(tx)?.iter().any(|o| o.amount <= 0) {
+            return Err("Transaction amount must be greater than 0".to_string());
+        }
+        let cost = Transaction::cost(tx);
+
+        let wallets = Wallet::list_all(&FileOps {}, wallets_path).map_err(|e| e.to_string())?;
+        let balance = match wallets.iter().find(|w| w.address == from) {
+            Some(wallet) => wallet.balance,
+            None => return Err(format!("No wallet found with address {}", from)),
+        };
+        let base_data = FileOps::parse_validated(mempool_path, "transactions")?;
+        let pending: i64 = base_data["transactions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|t| t["from_address"] == from)
+            .map(Transaction::cost)
+            .fold(0i64, i64::saturating_add);
+
+        if pending.saturating_add(cost) > balance {
+            return Err(format!(
+                "Not enough funds: {} of {} is already pending and this transaction needs {}",
+                pending, balance, cost
+            ));
+        }
+        Ok(())
+    }
+
+    /// Collects every transaction in the chain that
+    /// sends from or pays to an address through any of its
+    /// outputs, in block order
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blockchain_path: &Path -> path to blockchain.json
+    /// address: &str          -> wallet address to look for
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<Value> -> empty if blockchain.json cannot be read
+    /// ```
+    pub fn history(blockchain_path: &Path, address: &str) -> Vec<Value> {
+        let base_data = match FileOps::parse(blockchain_path) {
+            Ok(data) => data,
+            Err(_) => return Vec::new(),
+        };
+        let blockchain = match base_data["blockchain"].as_array() {
+            Some(data) => data,
+            None => return Vec::new(),
         };
+        blockchain
+            .iter()
+            .filter_map(|block| block["transactions"]["transactions"].as_array())
+            .flatten()
+            .filter(|t| {
+                t["from_address"] == address
+                    || Transaction::outputs(t)
+                        .unwrap_or_default()
+                        .iter()
+                        .any(|o| o.to == address)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_transaction {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::mods::{
+        base::Blockchain,
+        block::{BLOCK_REWARD, DIFFICULTY},
+        config::Config,
+        helpers::mine_block,
+        log::Log,
+        mempool,
+    };
+
+    fn signed_outputs(sender: &KeyPair, outputs: Value) -> Value {
+        let mut tx = json!({ "from_address": sender.public_key, "outputs": outputs, "fee": 1 });
+        tx["hash"] = Value::from(Transaction::content_hash(&tx));
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            sender.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+        tx
+    }
+
+    fn signed(sender: &KeyPair, signer: &KeyPair, to: &str, amount: i64) -> Value {
+        let hash = hash_transaction(&sender.public_key, to, &amount.to_string());
+        let mut tx = json!({
+            "hash": hash,
+            "from_address": sender.public_key,
+            "to_address": to,
+            "amount": amount,
+            "fee": 1,
+        });
+        let (signature, _) = KeyPair::sign(
+            &Transaction::canonical_bytes(&tx),
+            signer.private_key.clone(),
+        );
+        tx["signature"] = Value::from(signature);
+        tx
+    }
+
+    #[test]
+    fn test_verify() {
+        let alice = KeyPair::from_seed(String::from("alice"), &[1u8; 32]);
+        let mallory = KeyPair::from_seed(String::from("mallory"), &[2u8; 32]);
+        let path = temp_dir().join("mockchain_test_transaction_verify.json");
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": alice.public_key, "balance": 100 },
+        ]});
+        fs::write(&path, wallets.to_string()).unwrap();
+
+        let valid = Transaction::verify(&signed(&alice, &alice, "bob", 10), &path);
+        let forged = Transaction::verify(&signed(&alice, &mallory, "bob", 10), &path);
+        let unknown = Transaction::verify(&signed(&mallory, &mallory, "bob", 10), &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Ok(()), valid);
+        assert!(forged.is_err());
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_verify_shuffled_keys() {
+        let alice = KeyPair::from_seed(String::from("alice"), &[1u8; 32]);
+        let path = temp_dir().join("mockchain_test_transaction_shuffled.json");
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": alice.public_key, "balance": 100 },
+        ]});
+        fs::write(&path, wallets.to_string()).unwrap();
+        let tx = signed(&alice, &alice, "bob", 10);
+        let shuffled = format!(
+            r#"{{"signature":{},"fee":{},"amount":{},"to_address":{},"hash":{},"from_address":{}}}"#,
+            tx["signature"],
+            tx["fee"],
+            tx["amount"],
+            tx["to_address"],
+            tx["hash"],
+            tx["from_address"]
+        );
+        let shuffled: Value = serde_json::from_str(&shuffled).unwrap();
+        let mut raised_fee = tx.clone();
+        raised_fee["fee"] = Value::from(9);
+
+        let valid = Transaction::verify(&shuffled, &path);
+        let tampered = Transaction::verify(&raised_fee, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Transaction::canonical_bytes(&tx),
+            Transaction::canonical_bytes(&shuffled)
+        );
+        assert_eq!(Ok(()), valid);
+        assert!(tampered.is_err());
+    }
+
+    #[test]
+    fn test_multi_output() {
+        let alice = KeyPair::from_seed(String::from("alice"), &[1u8; 32]);
+        let wallets_path = temp_dir().join("mockchain_test_transaction_outputs.json");
+        let mempool_path = temp_dir().join("mockchain_test_transaction_outputs_mempool.json");
+        let wallets = json!({ "wallets": [
+            { "name": "alice", "address": alice.public_key, "balance": 20 },
+        ]});
+        fs::write(&wallets_path, wallets.to_string()).unwrap();
+        fs::write(&mempool_path, json!({ "transactions": [] }).to_string()).unwrap();
+        let tx = signed_outputs(
+            &alice,
+            json!([{ "to": "bob", "amount": 10 }, { "to": "carol", "amount": 5 }]),
+        );
+        let mut tampered = tx.clone();
+        tampered["outputs"][1]["amount"] = Value::from(6);
+        let overspend = signed_outputs(
+            &alice,
+            json!([{ "to": "bob", "amount": 10 }, { "to": "carol", "amount": 15 }]),
+        );
+
+        let valid = Transaction::verify(&tx, &wallets_path);
+        let forged = Transaction::verify(&tampered, &wallets_path);
+        let affordable = Transaction::check_spendable(&tx, &wallets_path, &mempool_path);
+        let rejected = Transaction::check_spendable(&overspend, &wallets_path, &mempool_path);
+        fs::remove_file(&wallets_path).unwrap();
+        fs::remove_file(&mempool_path).unwrap();
+
+        assert_eq!(Ok(()), valid);
+        assert!(forged.is_err());
+        assert_eq!(16, Transaction::cost(&tx));
+        assert_eq!(Ok(()), affordable);
+        assert_eq!(
+            Err(
+                "Not enough funds: 0 of 20 is already pending and this transaction needs 26"
+                    .to_string()
+            ),
+            rejected
+        );
+    }
+
+    #[test]
+    fn test_multi_output_mined() {
+        Log::init();
+        let config = Config::new(&temp_dir().join("mockchain_test_transaction_outputs_mined"));
+        FileOps::init(&config, false);
+        let mut addresses = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            let wallet = Wallet::new(
+                &config.wallets_path,
+                &config.keypairs_path,
+                name.to_string(),
+            )
+            .unwrap();
+            addresses.push(wallet.address);
+        }
+        FileOps::write_balance(
+            &config.wallets_path,
+            FileOps::parse(&config.wallets_path).unwrap(),
+            addresses[0].clone(),
+            100,
+        )
+        .unwrap();
+        let alice = KeyPair::load(&config.keypairs_path, "alice").unwrap();
+        let tx = signed_outputs(
+            &alice,
+            json!([
+                { "to": addresses[1], "amount": 10 },
+                { "to": addresses[2], "amount": 15 },
+            ]),
+        );
+
+        mempool::add(&config, tx).unwrap();
+        mine_block(&config, String::from("carol")).unwrap();
+        let balance = |name: &str| {
+            Wallet::get_balance(&FileOps {}, &config.wallets_path, name)
+                .unwrap()
+                .unwrap()
+        };
+        let balances = [balance("alice"), balance("bob"), balance("carol")];
+        let valid = Blockchain::validate_chain(&config.blockchain_path, DIFFICULTY);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!([74, 10, 15 + BLOCK_REWARD as i64 + 1], balances);
+        assert_eq!(Ok(()), valid);
+    }
+
+    #[test]
+    fn test_invalid_amounts() {
+        let negative = json!({ "from_address": "a", "to_address": "b", "amount": -10 });
+        let negative_output = json!({ "from_address": "a", "outputs": [
+            { "to": "b", "amount": 4 },
+            { "to": "c", "amount": -1 },
+        ]});
+        let overflowing = json!({ "from_address": "a", "outputs": [
+            { "to": "b", "amount": i64::MAX },
+            { "to": "c", "amount": 1 },
+        ]});
+        let mut fee_overflow =
+            json!({ "from_address": "a", "to_address": "b", "amount": i64::MAX });
+        fee_overflow["fee"] = json!(5);
+
+        assert!(Transaction::outputs(&negative).is_err());
+        assert!(Transaction::outputs(&negative_output).is_err());
+        assert!(Transaction::outputs(&overflowing)
+            .unwrap_err()
+            .contains("overflows"));
+        assert_eq!(i64::MAX, Transaction::cost(&fee_overflow));
+    }
+
+    #[test]
+    fn test_txid() {
+        let tx = json!({ "from_address": "a", "to_address": "b", "amount": 10, "fee": 1 });
+        let shuffled: Value =
+            serde_json::from_str(r#"{"fee":1,"amount":10,"to_address":"b","from_address":"a"}"#)
+                .unwrap();
+        let mut other = tx.clone();
+        other["amount"] = Value::from(11);
+
+        let mut first = tx.clone();
+        first["timestamp"] = Value::from("2024-06-01T00:00:00+00:00");
+        let mut repeat = tx.clone();
+        repeat["timestamp"] = Value::from("2024-06-01T00:05:00+00:00");
+
+        assert_eq!(Transaction::txid(&tx), Transaction::txid(&shuffled));
+        assert_ne!(Transaction::txid(&tx), Transaction::txid(&other));
+        assert_ne!(Transaction::txid(&first), Transaction::txid(&repeat));
+        assert_ne!(Transaction::txid(&tx), Transaction::txid(&first));
+        assert_eq!(64, Transaction::txid(&tx).len());
+    }
+
+    #[test]
+    fn test_history() {
+        let path = temp_dir().join("mockchain_test_transaction_history.json");
+        let t = |from: &str, to: &str, amount: i64| json!({ "from_address": from, "to_address": to, "amount": amount });
+        let blockchain = json!({ "blockchain": [
+            { "transactions": { "transactions": [] } },
+            { "transactions": { "transactions": [t("COINBASE", "alice", 50)] } },
+            { "transactions": { "transactions": [t("alice", "bob", 20), t("carol", "dave", 5)] } },
+            { "transactions": { "transactions": [t("dave", "alice", 3), t("bob", "carol", 1)] } },
+        ]});
+        fs::write(&path, blockchain.to_string()).unwrap();
+
+        let alice = Transaction::history(&path, "alice");
+        let erin = Transaction::history(&path, "erin");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            vec![
+                t("COINBASE", "alice", 50),
+                t("alice", "bob", 20),
+                t("dave", "alice", 3)
+            ],
+            alice
+        );
+        assert!(erin.is_empty());
     }
 }
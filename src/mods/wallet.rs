@@ -1,14 +1,30 @@
+// std library
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+
 // 3rd party crates
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, json, to_value, Map, Value};
 
 // imports
 use super::{
-    file::FileOps,
-    log::{Log, LogLevel},
+    amount::Amount,
+    block::COINBASE_ADDRESS,
+    crypto::KeyPair,
+    events::{Event, LedgerEvents},
+    file::{FileOps, FileOpsError},
+    storage::Storage,
+    transaction::Transaction,
 };
-use crate::WALLETS_PATH;
 
-/// Defines a Wallet object with name, address, and balance
+// reads of wallets.json retried while another writer holds it, and the first delay
+const READ_ATTEMPTS: u32 = 3;
+const READ_BACKOFF_MS: u64 = 10;
+
+/// Defines a Wallet object with name, address, and balance.
+/// A frozen wallet can receive funds but not send them
 ///
 /// # Visibility
 /// public
@@ -17,50 +33,235 @@ use crate::WALLETS_PATH;
 /// ```
 /// name: String
 /// address: String
-/// balance: u32
+/// balance: i64
+/// frozen: bool
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Wallet {
     pub name: String,
     pub address: String,
-    pub balance: i32,
+    pub balance: i64,
+    #[serde(default)]
+    pub frozen: bool,
 }
 
 impl Wallet {
-    /// Checks to see if a name has already been used
+    /// Creates a new account. A KeyPair is generated and
+    /// appended to keypairs.json, and the wallet is given
+    /// the public key as its address and a zero balance
+    /// before being appended to wallets.json
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: &String -> name to check for
+    /// path: &Path          -> path to wallets.json
+    /// keypairs_path: &Path -> path to keypairs.json
+    /// name: String         -> name of the account
     /// ```
     ///
     /// # Returns
     /// ```
-    /// bool
+    /// Result<Wallet, String>
     /// ```
-    pub fn name_exists(name: &String) -> bool {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
+    pub fn new(path: &Path, keypairs_path: &Path, name: String) -> Result<Wallet, String> {
+        let wallet = Wallet::insert(path, keypairs_path, KeyPair::generate(name))?;
+        log::info!("Wallet created for '{}'", wallet.name);
+        Ok(wallet)
+    }
+
+    /// Imports an account from a private key created
+    /// elsewhere. The public key and address are derived
+    /// from it and the key pair and a zero-balance wallet
+    /// are appended to keypairs.json and wallets.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path          -> path to wallets.json
+    /// keypairs_path: &Path -> path to keypairs.json
+    /// name: String         -> name of the account
+    /// private_key: &str    -> hex encoded private key
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Wallet, String>
+    /// ```
+    pub fn import(
+        path: &Path,
+        keypairs_path: &Path,
+        name: String,
+        private_key: &str,
+    ) -> Result<Wallet, String> {
+        let key_pair = KeyPair::from_private_key(name, private_key)?;
+        let wallet = Wallet::insert(path, keypairs_path, key_pair)?;
+        log::info!("Wallet imported for '{}'", wallet.name);
+        Ok(wallet)
+    }
+
+    /// Appends a key pair to keypairs.json and a zero-balance
+    /// wallet for it to wallets.json. The name and address
+    /// checks and the writes happen under one lock so two
+    /// callers cannot both claim the same name
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path          -> path to wallets.json
+    /// keypairs_path: &Path -> path to keypairs.json
+    /// key_pair: KeyPair    -> key pair for the new wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Wallet, String>
+    /// ```
+    fn insert(path: &Path, keypairs_path: &Path, key_pair: KeyPair) -> Result<Wallet, String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "wallets")?;
+            let mut keypair_data = FileOps::parse_validated(keypairs_path, "keypairs")?;
+            let (wallets, keypairs) = match (
+                base_data["wallets"].as_array_mut(),
+                keypair_data["keypairs"].as_array_mut(),
+            ) {
+                (Some(wallets), Some(keypairs)) => (wallets, keypairs),
+                _ => return Err("Failed to read wallets.json or keypairs.json".to_string()),
+            };
+
+            if wallets.iter().any(|w| w["name"] == *key_pair.name) {
+                return Err(format!(
+                    "Wallet with name '{}' already exists",
+                    key_pair.name
+                ));
             }
-        };
+            if let Some(existing) = wallets
+                .iter()
+                .find(|w| w["address"] == *key_pair.public_key)
+            {
+                return Err(format!(
+                    "This key already belongs to the wallet '{}'",
+                    existing["name"].as_str().unwrap_or_default()
+                ));
+            }
+
+            let wallet = Wallet {
+                name: key_pair.name.clone(),
+                address: key_pair.public_key.clone(),
+                balance: 0,
+                frozen: false,
+            };
+            keypairs.push(to_value(&key_pair).map_err(|e| e.to_string())?);
+            wallets.push(to_value(&wallet).map_err(|e| e.to_string())?);
+
+            FileOps::overwrite(keypairs_path, &keypair_data).map_err(|e| e.to_string())?;
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            Wallet::refresh_index(&FileOps {}, path).map_err(|e| e.to_string())?;
+            Ok(wallet)
+        })
+    }
+
+    /// Creates several accounts with one read and write
+    /// of wallets.json and keypairs.json. The whole batch
+    /// is rejected if any name already exists or appears
+    /// twice in `names`
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path          -> path to wallets.json
+    /// keypairs_path: &Path -> path to keypairs.json
+    /// names: &[String]     -> names of the accounts
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Wallet>, String>
+    /// ```
+    pub fn create_many(
+        path: &Path,
+        keypairs_path: &Path,
+        names: &[String],
+    ) -> Result<Vec<Wallet>, String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "wallets")?;
+            let mut keypair_data = FileOps::parse_validated(keypairs_path, "keypairs")?;
+            let (wallets, keypairs) = match (
+                base_data["wallets"].as_array_mut(),
+                keypair_data["keypairs"].as_array_mut(),
+            ) {
+                (Some(wallets), Some(keypairs)) => (wallets, keypairs),
+                _ => return Err("Failed to read wallets.json or keypairs.json".to_string()),
+            };
+
+            for (i, name) in names.iter().enumerate() {
+                if wallets.iter().any(|w| w["name"] == *name) || names[..i].contains(name) {
+                    return Err(format!("Wallet with name '{}' already exists", name));
+                }
+            }
+
+            let mut created = Vec::new();
+            for name in names {
+                let key_pair = KeyPair::generate(name.clone());
+                let wallet = Wallet {
+                    name: name.clone(),
+                    address: key_pair.public_key.clone(),
+                    balance: 0,
+                    frozen: false,
+                };
+                keypairs.push(to_value(&key_pair).map_err(|e| e.to_string())?);
+                wallets.push(to_value(&wallet).map_err(|e| e.to_string())?);
+                created.push(wallet);
+            }
+
+            FileOps::overwrite(keypairs_path, &keypair_data).map_err(|e| e.to_string())?;
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            Wallet::refresh_index(&FileOps {}, path).map_err(|e| e.to_string())?;
+            log::info!("{} wallets created", created.len());
+            Ok(created)
+        })
+    }
+
+    /// Checks to see if a name has already been used
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// name: &str            -> name to check for
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, FileOpsError>
+    /// ```
+    pub fn name_exists(
+        storage: &dyn Storage,
+        path: &Path,
+        name: &str,
+    ) -> Result<bool, FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
         for wallet in wallets {
             if wallet["name"] == *name {
-                return true;
+                return Ok(true);
             }
         }
-        false
+        Ok(false)
     }
 
     /// Reads the public key address of a wallet from
@@ -71,77 +272,277 @@ impl Wallet {
     ///
     /// # Args
     /// ```
-    /// name: String -> name to get address of
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// name: &str            -> name to get address of
     /// ```
     ///
     /// # Returns
     /// ```
-    /// Option<String>
+    /// Result<Option<String>, FileOpsError>
     /// ```
-    pub fn get_wallet_address(name: &String) -> Option<String> {
-        if !Wallet::name_exists(name) {
-            None
+    pub fn get_wallet_address(
+        storage: &dyn Storage,
+        path: &Path,
+        name: &str,
+    ) -> Result<Option<String>, FileOpsError> {
+        if !Wallet::name_exists(storage, path, name)? {
+            Ok(None)
         } else {
-            let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-            let wallets = match base_data["wallets"].as_array_mut() {
-                Some(data) => data,
-                None => {
-                    Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                    panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-                }
-            };
+            let wallets = Wallet::read_all(storage, path)?;
 
             let mut wallet_name = String::from("");
 
             for wallet in wallets {
                 if wallet["name"] == *name {
-                    wallet_name.push_str(wallet["address"].to_string().as_str());
+                    if let Some(address) = wallet["address"].as_str() {
+                        wallet_name.push_str(address);
+                    }
                 }
             }
-            Some(wallet_name)
+            Ok(Some(wallet_name))
+        }
+    }
+
+    /// Finds a wallet by its address using the position
+    /// recorded in wallets_index.json. On any miss, or if
+    /// the index is missing or no longer matches the
+    /// wallets, wallets.json is scanned and the index rebuilt
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// address: &str         -> wallet address to find
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Option<Wallet>, FileOpsError>
+    /// ```
+    pub fn get_by_address(
+        storage: &dyn Storage,
+        path: &Path,
+        address: &str,
+    ) -> Result<Option<Wallet>, FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
+        let index = storage
+            .read(&Wallet::index_path(path))
+            .ok()
+            .and_then(|data| data["index"].as_object().cloned())
+            .filter(|index| index.len() == wallets.len());
+
+        let indexed = index
+            .and_then(|index| index.get(address).and_then(Value::as_u64))
+            .and_then(|i| wallets.get(i as usize))
+            .filter(|wallet| wallet["address"] == address);
+        if let Some(wallet) = indexed {
+            return Ok(from_value(wallet.clone()).ok());
         }
+
+        // a miss may just mean the index is stale so it is rebuilt from a scan
+        let _ = Wallet::write_index(storage, path, &wallets);
+        Ok(wallets
+            .iter()
+            .find(|w| w["address"] == address)
+            .and_then(|w| from_value(w.clone()).ok()))
     }
 
     /// Updates the value of the wallet balance after
-    /// a transaction has been added to a block
+    /// a transaction has been added to a block. A
+    /// subtraction that would take the balance below
+    /// zero is rejected and nothing is written
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
+    /// path: &Path     -> path to wallets.json
     /// address: String -> wallet address to update
-    /// amount: i32     -> amount to increment balance by
+    /// amount: i64     -> amount to increment balance by
     /// op: &str        -> "add" | "subtract"
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn update_balance(address: String, amount: i32, op: &str) {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
+    /// ```
+    /// Result<i64, String> -> the new balance
+    /// ```
+    pub fn update_balance(
+        path: &Path,
+        address: String,
+        amount: i64,
+        op: &str,
+    ) -> Result<i64, String> {
+        let balance = FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "wallets")?;
+            let balance = match Wallet::apply_balance(&mut base_data, &address, amount, op) {
+                Ok(balance) => balance,
+                Err(e) => {
+                    log::warn!(
+                        "Balance of {} not updated: {}",
+                        address.trim_matches('"'),
+                        e
+                    );
+                    return Err(e);
+                }
+            };
+            FileOps::write_balance(path, base_data, address.clone(), balance)
+                .map_err(|e| e.to_string())?;
+            log::info!(
+                "Balance of {} is now {} after {} {}",
+                address.trim_matches('"'),
+                balance,
+                op,
+                amount
+            );
+            Ok(balance)
+        })?;
+        LedgerEvents::emit(Event::BalanceUpdated {
+            path: path.to_path_buf(),
+            address: address.trim_matches('"').to_string(),
+            balance,
+        });
+        Ok(balance)
+    }
+
+    /// Runs the same checks as update_balance and returns
+    /// the balance it would leave without writing anything
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path    -> path to wallets.json
+    /// address: &str  -> wallet address to update
+    /// amount: i64    -> amount to increment balance by
+    /// op: &str       -> "add" | "subtract"
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i64, String> -> the projected balance
+    /// ```
+    pub fn preview_update_balance(
+        path: &Path,
+        address: &str,
+        amount: i64,
+        op: &str,
+    ) -> Result<i64, String> {
+        let mut base_data = FileOps::parse_validated(path, "wallets")?;
+        Wallet::apply_balance(&mut base_data, address, amount, op)
+    }
+
+    /// Applies a balance update to parsed wallets.json data
+    /// in memory
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// base_data: &mut Value -> parsed wallets.json
+    /// address: &str         -> wallet address to update
+    /// amount: i64           -> amount to increment balance by
+    /// op: &str              -> "add" | "subtract"
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i64, String> -> the new balance
+    /// ```
+    pub fn apply_balance(
+        base_data: &mut Value,
+        address: &str,
+        amount: i64,
+        op: &str,
+    ) -> Result<i64, String> {
+        if op != "add" && op != "subtract" {
+            return Err(format!(
+                "Invalid balance operation '{}', expected 'add' or 'subtract'",
+                op
+            ));
+        }
+        let amount = Amount::new(amount)?;
         let wallets = match base_data["wallets"].as_array_mut() {
             Some(data) => data,
             None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
+                return Err("Failed to read wallets.json, has the data been modified or the file moved or deleted?".to_string());
             }
         };
 
         for wallet in wallets {
-            if wallet["address"].to_string() == address {
+            if wallet["address"] == address.trim_matches('"') {
                 if let Some(val) = wallet["balance"].as_i64() {
-                    let mut balance = val as i32;
+                    let mut balance = Amount::new(val)?;
                     if op == "add" {
-                        balance += amount;
+                        balance = balance.checked_add(amount)?;
                     }
                     if op == "subtract" {
-                        balance -= amount;
+                        if wallet["frozen"] == true {
+                            return Err(format!(
+                                "Wallet with address {} is frozen",
+                                address.trim_matches('"')
+                            ));
+                        }
+                        balance = balance.checked_sub(amount).map_err(|_| {
+                            format!(
+                                "Not enough funds to subtract {} from a balance of {}",
+                                amount, balance
+                            )
+                        })?;
                     }
-                    FileOps::write_balance(address, balance);
-                    break;
+                    wallet["balance"] = Value::from(balance.value());
+                    return Ok(balance.value());
                 }
             }
         }
+        Err(format!("No wallet found with address {}", address))
+    }
+
+    /// Applies a mined transaction to parsed wallets.json
+    /// data in memory. The sender is charged the outputs
+    /// plus the fee and each output is paid to its
+    /// recipient, a coinbase only pays its outputs. Nothing
+    /// is changed if any part is rejected, e.g. a sender
+    /// without enough funds or an unknown recipient
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// base_data: &mut Value -> parsed wallets.json
+    /// tx: &Value            -> transaction JSON to apply
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<(String, i64)>, String> -> address and new balance of each wallet updated
+    /// ```
+    pub fn apply_transaction(
+        base_data: &mut Value,
+        tx: &Value,
+    ) -> Result<Vec<(String, i64)>, String> {
+        let outputs = Transaction::outputs(tx)?;
+        let from = tx["from_address"].as_str().unwrap_or_default();
+        let mut updated = base_data.clone();
+        let mut balances = Vec::new();
+        if from != COINBASE_ADDRESS {
+            let cost = Transaction::cost(tx);
+            let balance = Wallet::apply_balance(&mut updated, from, cost, "subtract")?;
+            balances.push((from.to_string(), balance));
+        }
+        for output in outputs {
+            let balance = Wallet::apply_balance(&mut updated, &output.to, output.amount, "add")?;
+            balances.push((output.to, balance));
+        }
+        *base_data = updated;
+        Ok(balances)
     }
 
     /// Gets the current balance of this Wallet
@@ -151,32 +552,1457 @@ impl Wallet {
     ///
     /// # Args
     /// ```
-    /// name: &String -> name of account to lookup
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// name: &str            -> name of account to lookup
     /// ```
     ///
     /// # Returns
     /// ```
-    /// i32
+    /// Result<Option<i64>, FileOpsError>
     /// ```
-    pub fn get_balance(name: &String) -> i32 {
-        let mut balance: i32 = 0;
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-            }
-        };
+    pub fn get_balance(
+        storage: &dyn Storage,
+        path: &Path,
+        name: &str,
+    ) -> Result<Option<i64>, FileOpsError> {
+        let mut balance: Option<i64> = None;
+        let wallets = Wallet::read_all(storage, path)?;
 
         for wallet in wallets {
             if wallet["name"] == *name {
                 if let Some(val) = wallet["balance"].as_i64() {
-                    balance = val as i32;
+                    balance = Some(val);
                     break;
                 }
             }
         }
+        if balance.is_none() {
+            log::warn!("No balance found for wallet '{}'", name);
+        }
+        Ok(balance)
+    }
+
+    /// Gets the balances of several accounts with a single
+    /// read of wallets.json. Unknown names map to None, in
+    /// the same order as `names`
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// names: &[String]      -> names of accounts to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<(String, Option<i64>)>, FileOpsError>
+    /// ```
+    pub fn get_balances(
+        storage: &dyn Storage,
+        path: &Path,
+        names: &[String],
+    ) -> Result<Vec<(String, Option<i64>)>, FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
+        let mut balances = HashMap::new();
+        for wallet in &wallets {
+            if let (Some(name), Some(balance)) =
+                (wallet["name"].as_str(), wallet["balance"].as_i64())
+            {
+                // the first wallet with a name wins, as in get_balance
+                balances.entry(name).or_insert(balance);
+            }
+        }
+        Ok(names
+            .iter()
+            .map(|name| (name.clone(), balances.get(name.as_str()).copied()))
+            .collect())
+    }
+
+    /// Sums the balance of every wallet. Coins are only
+    /// created by block rewards, as fees move between
+    /// wallets, so this should equal the rewards paid out
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i64, FileOpsError> -> 0 when there are no wallets
+    /// ```
+    pub fn total_supply(storage: &dyn Storage, path: &Path) -> Result<i64, FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
+        Ok(wallets.iter().filter_map(|w| w["balance"].as_i64()).sum())
+    }
+
+    /// Rebuilds the balance of an address from the chain
+    /// alone by replaying every transaction, coinbase
+    /// credits included, from the genesis block up to and
+    /// including block `height`. Each output pays its
+    /// recipient and senders are charged the outputs plus
+    /// the fee once
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blockchain_path: &Path -> path to blockchain.json
+    /// address: &str          -> wallet address to replay
+    /// height: usize          -> index of the last block to include
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i64 -> 0 if blockchain.json cannot be read
+    /// ```
+    pub fn balance_at(blockchain_path: &Path, address: &str, height: usize) -> i64 {
+        let base_data = match FileOps::parse_validated(blockchain_path, "blockchain") {
+            Ok(data) => data,
+            Err(_) => return 0,
+        };
+        let blocks = base_data["blockchain"].as_array().into_iter().flatten();
+
+        let mut balance = 0;
+        for block in blocks.take(height.saturating_add(1)) {
+            let transactions = block["transactions"]["transactions"].as_array();
+            for t in transactions.into_iter().flatten() {
+                for output in Transaction::outputs(t).unwrap_or_default() {
+                    if output.to == address {
+                        balance += output.amount;
+                    }
+                }
+                if t["from_address"] == address {
+                    balance -= Transaction::cost(t);
+                }
+            }
+        }
         balance
     }
+
+    /// Reads every wallet stored in wallets.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Wallet>, FileOpsError>
+    /// ```
+    pub fn list_all(storage: &dyn Storage, path: &Path) -> Result<Vec<Wallet>, FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
+
+        let mut result = Vec::new();
+        for wallet in wallets {
+            result.push(from_value(wallet)?);
+        }
+        Ok(result)
+    }
+
+    /// Writes every wallet to a CSV file with a
+    /// name,address,balance header for use in a spreadsheet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// out: &Path            -> path of the CSV file to write
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn export_csv(storage: &dyn Storage, path: &Path, out: &Path) -> Result<(), String> {
+        let wallets = Wallet::list_all(storage, path).map_err(|e| e.to_string())?;
+        let mut csv = String::from("name,address,balance\r\n");
+        for wallet in wallets {
+            csv.push_str(&format!(
+                "{},{},{}\r\n",
+                Wallet::csv_field(&wallet.name),
+                Wallet::csv_field(&wallet.address),
+                wallet.balance
+            ));
+        }
+        fs::write(out, csv).map_err(|e| e.to_string())
+    }
+
+    /// Quotes a CSV field if it holds a comma, quote or
+    /// line break, doubling any quotes inside it
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// field: &str -> value to write
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// String
+    /// ```
+    fn csv_field(field: &str) -> String {
+        if field.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    /// Removes a wallet from wallets.json, keeping the
+    /// surrounding {"wallets": [...]} structure intact
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// name: &str            -> name of the account to remove
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn delete(storage: &dyn Storage, path: &Path, name: &str) -> Result<(), String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = storage
+                .read(path)
+                .and_then(|data| FileOps::validate(data, "wallets"))
+                .map_err(|e| e.to_string())?;
+            let wallets = match base_data["wallets"].as_array_mut() {
+                Some(data) => data,
+                None => {
+                    return Err("Failed to read wallets.json, has the data been modified or the file moved or deleted?".to_string());
+                }
+            };
+
+            let count = wallets.len();
+            wallets.retain(|w| w["name"] != *name);
+            if wallets.len() == count {
+                return Err(format!("No wallet found under name '{}'", name));
+            }
+
+            storage.write(path, &base_data).map_err(|e| e.to_string())?;
+            Wallet::refresh_index(storage, path).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Renames a wallet, keeping account names unique.
+    /// Only wallets.json is updated; key pairs and
+    /// signing data recorded under the old name in
+    /// keypairs.json and signing.json are left as they are
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// old: &str             -> current name of the account
+    /// new: &str             -> name to give the account
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn rename(storage: &dyn Storage, path: &Path, old: &str, new: &str) -> Result<(), String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = storage
+                .read(path)
+                .and_then(|data| FileOps::validate(data, "wallets"))
+                .map_err(|e| e.to_string())?;
+            let wallets = match base_data["wallets"].as_array_mut() {
+                Some(data) => data,
+                None => {
+                    return Err("Failed to read wallets.json, has the data been modified or the file moved or deleted?".to_string());
+                }
+            };
+
+            if wallets.iter().any(|w| w["name"] == *new) {
+                return Err(format!("Wallet with name '{}' already exists", new));
+            }
+            match wallets.iter_mut().find(|w| w["name"] == *old) {
+                Some(wallet) => wallet["name"] = Value::from(new),
+                None => return Err(format!("No wallet found under name '{}'", old)),
+            };
+
+            storage.write(path, &base_data).map_err(|e| e.to_string())?;
+            Wallet::refresh_index(storage, path).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Freezes or unfreezes a wallet. A frozen wallet is
+    /// still credited but transfers and subtractions from
+    /// it are rejected
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path  -> path to wallets.json
+    /// name: &str   -> name of the account
+    /// frozen: bool -> whether outgoing funds are blocked
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn set_frozen(path: &Path, name: &str, frozen: bool) -> Result<(), String> {
+        FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "wallets")?;
+            let wallets = match base_data["wallets"].as_array_mut() {
+                Some(data) => data,
+                None => {
+                    return Err("Failed to read wallets.json, has the data been modified or the file moved or deleted?".to_string());
+                }
+            };
+            match wallets.iter_mut().find(|w| w["name"] == *name) {
+                Some(wallet) => wallet["frozen"] = Value::from(frozen),
+                None => return Err(format!("No wallet found under name '{}'", name)),
+            };
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            log::info!(
+                "Wallet '{}' {}",
+                name,
+                if frozen { "frozen" } else { "unfrozen" }
+            );
+            Ok(())
+        })
+    }
+
+    /// Moves funds from one wallet to another in a single
+    /// read and write of the wallets file so the ledger
+    /// is never left with only one side of the transfer
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path  -> path to wallets.json
+    /// from: String -> name of the sending account
+    /// to: String   -> name of the receiving account
+    /// amount: i64  -> amount to move
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), String>
+    /// ```
+    pub fn transfer(path: &Path, from: String, to: String, amount: i64) -> Result<(), String> {
+        let updates = FileOps::with_lock(path, || {
+            let mut base_data = FileOps::parse_validated(path, "wallets")?;
+            let balances = match Wallet::apply_transfer(&mut base_data, &from, &to, amount) {
+                Ok(balances) => balances,
+                Err(e) => {
+                    log::warn!(
+                        "Transfer of {} from '{}' to '{}' rejected: {}",
+                        amount,
+                        from,
+                        to,
+                        e
+                    );
+                    return Err(e);
+                }
+            };
+            FileOps::overwrite(path, &base_data).map_err(|e| e.to_string())?;
+            log::info!("Transferred {} from '{}' to '{}'", amount, from, to);
+            let address = |name: &str| {
+                base_data["wallets"]
+                    .as_array()
+                    .and_then(|wallets| wallets.iter().find(|w| w["name"] == name))
+                    .and_then(|w| w["address"].as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            Ok([(address(&from), balances.0), (address(&to), balances.1)])
+        })?;
+        for (address, balance) in updates {
+            LedgerEvents::emit(Event::BalanceUpdated {
+                path: path.to_path_buf(),
+                address,
+                balance,
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs the same checks as transfer and returns the
+    /// balances it would leave without writing anything,
+    /// so a caller can confirm a transfer will succeed
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to wallets.json
+    /// from: &str  -> name of the sending account
+    /// to: &str    -> name of the receiving account
+    /// amount: i64 -> amount to move
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(i64, i64), String> -> projected sender and recipient balances
+    /// ```
+    pub fn preview_transfer(
+        path: &Path,
+        from: &str,
+        to: &str,
+        amount: i64,
+    ) -> Result<(i64, i64), String> {
+        let mut base_data = FileOps::parse_validated(path, "wallets")?;
+        Wallet::apply_transfer(&mut base_data, from, to, amount)
+    }
+
+    /// Applies a transfer to parsed wallets.json data in
+    /// memory
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// base_data: &mut Value -> parsed wallets.json
+    /// from: &str            -> name of the sending account
+    /// to: &str              -> name of the receiving account
+    /// amount: i64           -> amount to move
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(i64, i64), String> -> new sender and recipient balances
+    /// ```
+    fn apply_transfer(
+        base_data: &mut Value,
+        from: &str,
+        to: &str,
+        amount: i64,
+    ) -> Result<(i64, i64), String> {
+        let amount = Amount::new(amount)?;
+        let wallets = match base_data["wallets"].as_array_mut() {
+            Some(data) => data,
+            None => {
+                return Err("Failed to read wallets.json, has the data been modified or the file moved or deleted?".to_string());
+            }
+        };
+
+        let sender = match wallets.iter().position(|w| w["name"] == from) {
+            Some(i) => i,
+            None => return Err(format!("No wallet found under name '{}'", from)),
+        };
+        let recipient = match wallets.iter().position(|w| w["name"] == to) {
+            Some(i) => i,
+            None => return Err(format!("No wallet found under name '{}'", to)),
+        };
+
+        if wallets[sender]["frozen"] == true {
+            return Err(format!("Wallet '{}' is frozen", from));
+        }
+        let sender_balance = Amount::new(wallets[sender]["balance"].as_i64().unwrap_or(0))?;
+        let sender_balance = sender_balance.checked_sub(amount).map_err(|_| {
+            format!(
+                "Not enough funds to send {} from {}'s account",
+                amount, from
+            )
+        })?;
+        let recipient_balance = Amount::new(wallets[recipient]["balance"].as_i64().unwrap_or(0))?;
+        let recipient_balance = if sender == recipient {
+            sender_balance.checked_add(amount)?
+        } else {
+            recipient_balance.checked_add(amount)?
+        };
+        wallets[sender]["balance"] = Value::from(sender_balance.value());
+        wallets[recipient]["balance"] = Value::from(recipient_balance.value());
+
+        Ok((
+            wallets[sender]["balance"].as_i64().unwrap_or(0),
+            wallets[recipient]["balance"].as_i64().unwrap_or(0),
+        ))
+    }
+
+    /// Rebuilds wallets_index.json from the wallets
+    /// currently stored in wallets.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), FileOpsError>
+    /// ```
+    pub fn refresh_index(storage: &dyn Storage, path: &Path) -> Result<(), FileOpsError> {
+        let wallets = Wallet::read_all(storage, path)?;
+        Wallet::write_index(storage, path, &wallets)
+    }
+
+    /// Writes wallets_index.json mapping each address to
+    /// its position in the wallets array
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// wallets: &[Value]     -> wallets in stored order
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), FileOpsError>
+    /// ```
+    fn write_index(
+        storage: &dyn Storage,
+        path: &Path,
+        wallets: &[Value],
+    ) -> Result<(), FileOpsError> {
+        let mut index = Map::new();
+        for (i, wallet) in wallets.iter().enumerate() {
+            if let Some(address) = wallet["address"].as_str() {
+                index.insert(address.to_string(), Value::from(i));
+            }
+        }
+        storage.write(&Wallet::index_path(path), &json!({ "index": index }))
+    }
+
+    /// Gets the path of the address index kept next to
+    /// a wallets file, e.g. wallets_index.json
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    fn index_path(path: &Path) -> PathBuf {
+        let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+        file_name.push("_index.json");
+        path.with_file_name(file_name)
+    }
+
+    /// Reads the wallet array from wallets.json, retrying
+    /// transient read errors, and returns an error if the
+    /// file does not have the expected {"wallets": [...]}
+    /// structure
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// storage: &dyn Storage -> data store holding wallets.json
+    /// path: &Path           -> path to wallets.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Value>, FileOpsError>
+    /// ```
+    fn read_all(storage: &dyn Storage, path: &Path) -> Result<Vec<Value>, FileOpsError> {
+        let base_data = FileOps::parse_retry(storage, path, READ_ATTEMPTS, READ_BACKOFF_MS)?;
+        let mut base_data = FileOps::validate(base_data, "wallets")?;
+        Ok(base_data["wallets"]
+            .as_array_mut()
+            .map(mem::take)
+            .unwrap_or_default())
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_wallet {
+    use super::*;
+
+    use std::env::temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use log::{Level, LevelFilter, Metadata, Record};
+    use serde_json::json;
+
+    use crate::mods::{
+        block::Block, config::Config, crypto::hash_transaction, storage::MemStorage,
+    };
+
+    // records every log entry so tests can assert on them
+    struct CaptureLogger;
+
+    static CAPTURED: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+    static LOGGER: CaptureLogger = CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            CAPTURED
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn write_wallets(file_name: &str, wallets: Value) -> PathBuf {
+        let path = temp_dir().join(file_name);
+        fs::write(&path, json!({ "wallets": wallets }).to_string()).unwrap();
+        path
+    }
+
+    fn balance_of(path: &Path, name: &str) -> Option<i64> {
+        let data = FileOps::parse(path).unwrap();
+        data["wallets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|w| w["name"] == name)
+            .and_then(|w| w["balance"].as_i64())
+    }
+
+    #[test]
+    fn test_name_exists() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let wallets = json!([{ "name": "alice", "address": "0".repeat(130), "balance": 0 }]);
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+
+        let alice = Wallet::name_exists(&storage, path, "alice");
+        let bob = Wallet::name_exists(&storage, path, "bob");
+
+        assert!(alice.unwrap());
+        assert!(!bob.unwrap());
+    }
+
+    #[test]
+    fn test_malformed_wallets() {
+        let path = temp_dir().join("mockchain_test_malformed_wallets.json");
+        fs::write(&path, json!({ "wallets": { "name": "alice" } }).to_string()).unwrap();
+
+        let exists = Wallet::name_exists(&FileOps {}, &path, "alice");
+        let updated = Wallet::update_balance(&path, "a".repeat(130), 10, "add");
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert!(matches!(exists, Err(FileOpsError::Schema(_))));
+        assert!(updated.is_err());
+    }
+
+    #[test]
+    fn test_new() {
+        let path = write_wallets("mockchain_test_new_wallets.json", json!([]));
+        let keypairs_path = temp_dir().join("mockchain_test_new_keypairs.json");
+        fs::write(&keypairs_path, json!({ "keypairs": [] }).to_string()).unwrap();
+
+        let wallet = Wallet::new(&path, &keypairs_path, String::from("alice")).unwrap();
+        let duplicate = Wallet::new(&path, &keypairs_path, String::from("alice"));
+        let exists = Wallet::name_exists(&FileOps {}, &path, "alice").unwrap();
+        let keypairs = FileOps::parse(&keypairs_path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&keypairs_path).unwrap();
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+        fs::remove_file(path.with_extension("json.lock")).unwrap();
+
+        assert!(exists);
+        assert!(duplicate.is_err());
+        assert_eq!(130, wallet.address.len());
+        assert_eq!(0, wallet.balance);
+        assert_eq!(1, keypairs["keypairs"].as_array().unwrap().len());
+        assert_eq!("alice", keypairs["keypairs"][0]["name"]);
+        assert_eq!(wallet.address, keypairs["keypairs"][0]["public_key"]);
+    }
+
+    #[test]
+    fn test_new_concurrent() {
+        let path = write_wallets("mockchain_test_new_concurrent.json", json!([]));
+        let keypairs_path = temp_dir().join("mockchain_test_new_concurrent_keypairs.json");
+        fs::write(&keypairs_path, json!({ "keypairs": [] }).to_string()).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (path, keypairs_path) = (path.clone(), keypairs_path.clone());
+                std::thread::spawn(move || {
+                    Wallet::new(&path, &keypairs_path, String::from("alice"))
+                })
+            })
+            .collect();
+        let created = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(Result::is_ok)
+            .count();
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        let keypairs = FileOps::parse(&keypairs_path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&keypairs_path).unwrap();
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+        fs::remove_file(path.with_extension("json.lock")).unwrap();
+
+        assert_eq!(1, created);
+        assert_eq!(1, wallets.len());
+        assert_eq!(1, keypairs["keypairs"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn test_import() {
+        let path = write_wallets("mockchain_test_import_wallets.json", json!([]));
+        let keypairs_path = temp_dir().join("mockchain_test_import_keypairs.json");
+        fs::write(&keypairs_path, json!({ "keypairs": [] }).to_string()).unwrap();
+        let original = KeyPair::generate(String::from("alice"));
+
+        let wallet = Wallet::import(
+            &path,
+            &keypairs_path,
+            String::from("alice"),
+            &original.private_key,
+        )
+        .unwrap();
+        let duplicate_name = Wallet::import(
+            &path,
+            &keypairs_path,
+            String::from("alice"),
+            &KeyPair::generate(String::new()).private_key,
+        );
+        let duplicate_key = Wallet::import(
+            &path,
+            &keypairs_path,
+            String::from("bob"),
+            &original.private_key,
+        );
+        let loaded = KeyPair::load(&keypairs_path, "alice");
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&keypairs_path).unwrap();
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+        fs::remove_file(path.with_extension("json.lock")).unwrap();
+
+        assert_eq!(original.public_key, wallet.address);
+        assert_eq!(0, wallet.balance);
+        assert!(duplicate_name.is_err());
+        assert!(duplicate_key.is_err());
+        assert_eq!(original.private_key, loaded.unwrap().private_key);
+    }
+
+    #[test]
+    fn test_import_malformed_key() {
+        let path = write_wallets("mockchain_test_import_malformed.json", json!([]));
+        let keypairs_path = temp_dir().join("mockchain_test_import_malformed_keypairs.json");
+        fs::write(&keypairs_path, json!({ "keypairs": [] }).to_string()).unwrap();
+
+        let not_hex = Wallet::import(&path, &keypairs_path, String::from("alice"), "xyz");
+        let too_short = Wallet::import(&path, &keypairs_path, String::from("alice"), "abcd");
+        let zero = Wallet::import(
+            &path,
+            &keypairs_path,
+            String::from("alice"),
+            &"0".repeat(64),
+        );
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        let keypairs = FileOps::parse(&keypairs_path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&keypairs_path).unwrap();
+
+        assert!(not_hex.unwrap_err().contains("not valid hex"));
+        assert!(too_short.is_err());
+        assert!(zero.is_err());
+        assert!(wallets.is_empty());
+        assert!(keypairs["keypairs"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_create_many() {
+        let path = write_wallets(
+            "mockchain_test_create_many.json",
+            json!([{ "name": "alice", "address": "a".repeat(130), "balance": 5 }]),
+        );
+        let keypairs_path = temp_dir().join("mockchain_test_create_many_keypairs.json");
+        fs::write(&keypairs_path, json!({ "keypairs": [] }).to_string()).unwrap();
+        let names: Vec<String> = (0..10).map(|i| format!("user{}", i)).collect();
+
+        let created = Wallet::create_many(&path, &keypairs_path, &names).unwrap();
+        let existing = Wallet::create_many(&path, &keypairs_path, &[String::from("alice")]);
+        let repeated = vec![String::from("carol"), String::from("carol")];
+        let in_batch = Wallet::create_many(&path, &keypairs_path, &repeated);
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        let keypairs = FileOps::parse(&keypairs_path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&keypairs_path).unwrap();
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+        fs::remove_file(path.with_extension("json.lock")).unwrap();
+
+        assert_eq!(10, created.len());
+        assert_eq!(11, wallets.len());
+        assert_eq!(10, keypairs["keypairs"].as_array().unwrap().len());
+        assert!(existing.is_err());
+        assert!(in_batch.is_err());
+    }
+
+    #[test]
+    fn test_missing_balance_warns() {
+        // a logger can only be installed once per process
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(LevelFilter::Info);
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let wallets = json!([{ "name": "alice", "address": "0".repeat(130), "balance": 5 }]);
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+
+        let balance = Wallet::get_balance(&storage, path, "ghost").unwrap();
+        let captured = CAPTURED.lock().unwrap();
+
+        assert_eq!(None, balance);
+        assert!(captured.iter().any(|(level, message)| {
+            *level == Level::Warn && message == "No balance found for wallet 'ghost'"
+        }));
+    }
+
+    #[test]
+    fn test_total_supply() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        storage.write(path, &json!({ "wallets": [] })).unwrap();
+        let empty = Wallet::total_supply(&storage, path).unwrap();
+
+        let wallets = json!([
+            { "name": "alice", "address": "a", "balance": 50 },
+            { "name": "bob", "address": "b", "balance": 30 },
+            { "name": "carol", "address": "c", "balance": 20 },
+        ]);
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+        let total = Wallet::total_supply(&storage, path).unwrap();
+
+        assert_eq!(0, empty);
+        assert_eq!(100, total);
+    }
+
+    #[test]
+    fn test_balance_at() {
+        let alice = "a".repeat(130);
+        let bob = "b".repeat(130);
+        let send = json!({
+            "hash": hash_transaction(&alice, &bob, "20"),
+            "from_address": alice,
+            "to_address": bob,
+            "amount": 20,
+            "fee": 1,
+            "signature": "",
+        });
+        let mut first = Block::new(Block::genesis().hash, vec![]);
+        first.add_coinbase(&alice, 50);
+        first.mine(2);
+        let mut second = Block::new(first.hash.clone(), vec![send]);
+        second.add_coinbase(&bob, 50);
+        second.mine(2);
+        let path = temp_dir().join("mockchain_test_balance_at.json");
+        let chain = json!({ "blockchain": [Block::genesis(), first, second] });
+        fs::write(&path, chain.to_string()).unwrap();
+
+        let alice_at_genesis = Wallet::balance_at(&path, &alice, 0);
+        let alice_at_first = Wallet::balance_at(&path, &alice, 1);
+        let alice_final = Wallet::balance_at(&path, &alice, 2);
+        let bob_final = Wallet::balance_at(&path, &bob, usize::MAX);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(0, alice_at_genesis);
+        assert_eq!(50, alice_at_first);
+        assert_eq!(29, alice_final);
+        assert_eq!(71, bob_final);
+    }
+
+    #[test]
+    fn test_get_wallet_address() {
+        let path = write_wallets(
+            "mockchain_test_get_wallet_address.json",
+            json!([{ "name": "alice", "address": "0".repeat(130), "balance": 0 }]),
+        );
+
+        let address = Wallet::get_wallet_address(&FileOps {}, &path, "alice").unwrap();
+        let missing = Wallet::get_wallet_address(&FileOps {}, &path, "bob").unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(130, address.unwrap().len());
+        assert_eq!(None, missing);
+    }
+
+    #[test]
+    fn test_get_by_address() {
+        let config = Config::new(&temp_dir().join("mockchain_test_get_by_address"));
+        FileOps::init(&config, false);
+        let names = ["alice", "bob", "carol"].map(String::from);
+        let created = Wallet::create_many(&config.wallets_path, &config.keypairs_path, &names);
+        let addresses: Vec<String> = created.unwrap().into_iter().map(|w| w.address).collect();
+        let index_path = Wallet::index_path(&config.wallets_path);
+
+        let index = FileOps::parse(&index_path).unwrap();
+        let bob = Wallet::get_by_address(&FileOps {}, &config.wallets_path, &addresses[1]);
+        Wallet::delete(&FileOps {}, &config.wallets_path, "alice").unwrap();
+        let moved = FileOps::parse(&index_path).unwrap();
+        let carol = Wallet::get_by_address(&FileOps {}, &config.wallets_path, &addresses[2]);
+        let alice = Wallet::get_by_address(&FileOps {}, &config.wallets_path, &addresses[0]);
+        fs::remove_dir_all(&config.data_dir).unwrap();
+
+        assert_eq!(1, index["index"][&addresses[1]]);
+        assert_eq!("bob", bob.unwrap().unwrap().name);
+        assert_eq!(1, moved["index"][&addresses[2]]);
+        assert_eq!("carol", carol.unwrap().unwrap().name);
+        assert!(alice.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_by_address_rebuild() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let index_path = Wallet::index_path(path);
+        let wallets = json!([
+            { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+            { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+        ]);
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+
+        // no index has been written yet
+        let missing = Wallet::get_by_address(&storage, path, &"1".repeat(130)).unwrap();
+        let rebuilt = storage.read(&index_path).unwrap();
+        let stale = json!({ "index": { "0".repeat(130): 1, "1".repeat(130): 0 } });
+        storage.write(&index_path, &stale).unwrap();
+        let swapped = Wallet::get_by_address(&storage, path, &"0".repeat(130)).unwrap();
+        let repaired = storage.read(&index_path).unwrap();
+
+        assert_eq!("bob", missing.unwrap().name);
+        assert_eq!(
+            json!({ "index": { "0".repeat(130): 0, "1".repeat(130): 1 } }),
+            rebuilt
+        );
+        assert_eq!("alice", swapped.unwrap().name);
+        assert_eq!(rebuilt, repaired);
+    }
+
+    #[test]
+    fn test_get_by_address_same_length() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let index_path = Wallet::index_path(path);
+        let alice = json!({ "name": "alice", "address": "0".repeat(130), "balance": 100 });
+        let bob = json!({ "name": "bob", "address": "1".repeat(130), "balance": 10 });
+        let carol = json!({ "name": "carol", "address": "2".repeat(130), "balance": 5 });
+        storage
+            .write(path, &json!({ "wallets": [alice.clone(), bob] }))
+            .unwrap();
+        Wallet::refresh_index(&storage, path).unwrap();
+
+        // bob is swapped for carol by hand so the index keeps its length
+        storage
+            .write(path, &json!({ "wallets": [alice, carol] }))
+            .unwrap();
+        let found = Wallet::get_by_address(&storage, path, &"2".repeat(130)).unwrap();
+        let rebuilt = storage.read(&index_path).unwrap();
+        let gone = Wallet::get_by_address(&storage, path, &"1".repeat(130)).unwrap();
+
+        assert_eq!("carol", found.unwrap().name);
+        assert_eq!(
+            json!({ "index": { "0".repeat(130): 0, "2".repeat(130): 1 } }),
+            rebuilt
+        );
+        assert!(gone.is_none());
+    }
+
+    #[test]
+    fn test_increment_balance() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_increment_balance.json",
+            json!([{ "name": "alice", "address": address, "balance": 100 }]),
+        );
+
+        let result = Wallet::update_balance(&path, address, 50, "add");
+        let after = balance_of(&path, "alice");
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Ok(150), result);
+        assert_eq!(Some(150), after);
+    }
+
+    #[test]
+    fn test_update_balance_concurrent() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_update_balance_concurrent.json",
+            json!([{ "name": "alice", "address": address, "balance": 0 }]),
+        );
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (path, address) = (path.clone(), address.clone());
+                std::thread::spawn(move || Wallet::update_balance(&path, address, 1, "add"))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        let after = balance_of(&path, "alice");
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("json.lock")).unwrap();
+
+        assert_eq!(Some(8), after);
+    }
+
+    #[test]
+    fn test_decrement_balance() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_decrement_balance.json",
+            json!([{ "name": "alice", "address": address, "balance": 20 }]),
+        );
+
+        let overdraft = Wallet::update_balance(&path, address.clone(), 50, "subtract");
+        let after_overdraft = balance_of(&path, "alice");
+        let invalid_op = Wallet::update_balance(&path, address.clone(), 5, "multiply");
+        let result = Wallet::update_balance(&path, address, 5, "subtract");
+        let after = balance_of(&path, "alice");
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert!(overdraft.is_err());
+        assert_eq!(Some(20), after_overdraft);
+        assert!(invalid_op.is_err());
+        assert_eq!(Ok(15), result);
+        assert_eq!(Some(15), after);
+    }
+
+    #[test]
+    fn test_get_balance() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_get_balance.json",
+            json!([{ "name": "alice", "address": address, "balance": 100 }]),
+        );
+
+        Wallet::update_balance(&path, address.clone(), 50, "add").unwrap();
+        Wallet::update_balance(&path, address, 20, "subtract").unwrap();
+        let balance = Wallet::get_balance(&FileOps {}, &path, "alice");
+        let missing = Wallet::get_balance(&FileOps {}, &path, "bob");
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Some(130), balance.unwrap());
+        assert_eq!(None, missing.unwrap());
+    }
+
+    #[test]
+    fn test_get_balance_missing_file() {
+        let path = temp_dir().join("mockchain_test_get_balance_missing.json");
+
+        let balance = Wallet::get_balance(&FileOps {}, &path, "alice");
+        let exists = Wallet::name_exists(&FileOps {}, &path, "alice");
+
+        assert!(matches!(balance, Err(FileOpsError::NotFound(p)) if p == path));
+        assert!(matches!(exists, Err(FileOpsError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_get_balances() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let wallets = json!([
+            { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+            { "name": "bob", "address": "1".repeat(130), "balance": 0 },
+            { "name": "carol", "address": "2".repeat(130), "balance": 7 },
+        ]);
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+        let names: Vec<String> = ["carol", "dave", "alice", "bob"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+
+        let balances = Wallet::get_balances(&storage, path, &names).unwrap();
+
+        assert_eq!(
+            vec![
+                ("carol".to_string(), Some(7)),
+                ("dave".to_string(), None),
+                ("alice".to_string(), Some(100)),
+                ("bob".to_string(), Some(0)),
+            ],
+            balances
+        );
+    }
+
+    #[test]
+    fn test_list_all() {
+        let path = write_wallets(
+            "mockchain_test_list_all.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+                { "name": "carol", "address": "2".repeat(130), "balance": 0 },
+            ]),
+        );
+
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(3, wallets.len());
+        let names: Vec<&str> = wallets.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(vec!["alice", "bob", "carol"], names);
+    }
+
+    // splits CSV text into rows of fields, undoing csv_field
+    fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let (mut row, mut field) = (Vec::new(), String::new());
+        let mut chars = csv.chars().peekable();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => row.push(mem::take(&mut field)),
+                ('\r', false) => {}
+                ('\n', false) => {
+                    row.push(mem::take(&mut field));
+                    rows.push(mem::take(&mut row));
+                }
+                _ => field.push(c),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let storage = MemStorage::new();
+        let path = Path::new("wallets.json");
+        let out = temp_dir().join("mockchain_test_export.csv");
+        let names = ["alice", "smith, bob", "carol \"cc\" jones", "dave,\"d\""];
+        let wallets: Vec<Value> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| json!({ "name": name, "address": i.to_string().repeat(130), "balance": i * 10 }))
+            .collect();
+        storage.write(path, &json!({ "wallets": wallets })).unwrap();
+
+        let result = Wallet::export_csv(&storage, path, &out);
+        let csv = fs::read_to_string(&out).unwrap();
+        fs::remove_file(&out).unwrap();
+
+        assert_eq!(Ok(()), result);
+        let rows = parse_csv(&csv);
+        assert_eq!(vec!["name", "address", "balance"], rows[0]);
+        assert_eq!(names.len() + 1, rows.len());
+        for (i, name) in names.iter().enumerate() {
+            let expected = [
+                name.to_string(),
+                i.to_string().repeat(130),
+                (i * 10).to_string(),
+            ];
+            assert_eq!(expected.to_vec(), rows[i + 1]);
+        }
+        assert!(csv.contains("\"carol \"\"cc\"\" jones\""));
+    }
+
+    #[test]
+    fn test_delete() {
+        let path = write_wallets(
+            "mockchain_test_delete.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let result = Wallet::delete(&FileOps {}, &path, "alice");
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(1, wallets.len());
+        assert_eq!("bob", wallets[0].name);
+    }
+
+    #[test]
+    fn test_delete_missing() {
+        let path = write_wallets(
+            "mockchain_test_delete_missing.json",
+            json!([{ "name": "alice", "address": "0".repeat(130), "balance": 100 }]),
+        );
+
+        let result = Wallet::delete(&FileOps {}, &path, "bob");
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(Err("No wallet found under name 'bob'".to_string()), result);
+        assert_eq!(1, wallets.len());
+    }
+
+    #[test]
+    fn test_rename() {
+        let path = write_wallets(
+            "mockchain_test_rename.json",
+            json!([{ "name": "alice", "address": "0".repeat(130), "balance": 100 }]),
+        );
+
+        let result = Wallet::rename(&FileOps {}, &path, "alice", "alicia");
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+        fs::remove_file(Wallet::index_path(&path)).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!("alicia", wallets[0].name);
+        assert_eq!(100, wallets[0].balance);
+    }
+
+    #[test]
+    fn test_rename_collision() {
+        let path = write_wallets(
+            "mockchain_test_rename_collision.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let result = Wallet::rename(&FileOps {}, &path, "alice", "bob");
+        let wallets = Wallet::list_all(&FileOps {}, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(
+            Err("Wallet with name 'bob' already exists".to_string()),
+            result
+        );
+        assert_eq!("alice", wallets[0].name);
+    }
+
+    #[test]
+    fn test_rename_missing() {
+        let path = write_wallets(
+            "mockchain_test_rename_missing.json",
+            json!([{ "name": "alice", "address": "0".repeat(130), "balance": 100 }]),
+        );
+
+        let result = Wallet::rename(&FileOps {}, &path, "carol", "dave");
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.lock"));
+
+        assert_eq!(
+            Err("No wallet found under name 'carol'".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn test_transfer() {
+        let path = write_wallets(
+            "mockchain_test_transfer.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let result = Wallet::transfer(&path, "alice".to_string(), "bob".to_string(), 40);
+        let (alice, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(Some(60), alice);
+        assert_eq!(Some(50), bob);
+    }
+
+    #[test]
+    fn test_preview_transfer() {
+        let path = write_wallets(
+            "mockchain_test_preview_transfer.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let projected = Wallet::preview_transfer(&path, "alice", "bob", 40);
+        let overdraft = Wallet::preview_transfer(&path, "bob", "alice", 40);
+        let missing = Wallet::preview_transfer(&path, "alice", "carol", 1);
+        let subtract = Wallet::preview_update_balance(&path, &"1".repeat(130), 40, "subtract");
+        let add = Wallet::preview_update_balance(&path, &"1".repeat(130), 40, "add");
+        let (alice, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Ok((60, 50)), projected);
+        assert!(overdraft.is_err());
+        assert!(missing.is_err());
+        assert!(subtract.is_err());
+        assert_eq!(Ok(50), add);
+        assert_eq!(Some(100), alice);
+        assert_eq!(Some(10), bob);
+    }
+
+    #[test]
+    fn test_negative_amounts() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_negative_amounts.json",
+            json!([
+                { "name": "alice", "address": address, "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let transfer = Wallet::transfer(&path, "alice".to_string(), "bob".to_string(), -40);
+        let subtract = Wallet::update_balance(&path, address.clone(), -5, "subtract");
+        let add = Wallet::update_balance(&path, address, -5, "add");
+        let (alice, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(transfer.unwrap_err().contains("must not be negative"));
+        assert!(subtract.is_err());
+        assert!(add.is_err());
+        assert_eq!(Some(100), alice);
+        assert_eq!(Some(10), bob);
+    }
+
+    #[test]
+    fn test_balance_overflow() {
+        let address = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_balance_overflow.json",
+            json!([
+                { "name": "alice", "address": address, "balance": i64::MAX - 5 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let add = Wallet::update_balance(&path, address.clone(), 10, "add");
+        let transfer = Wallet::transfer(&path, "bob".to_string(), "alice".to_string(), 10);
+        let fits = Wallet::update_balance(&path, address, 5, "add");
+        let (alice, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(add.unwrap_err().contains("overflows"));
+        assert!(transfer.unwrap_err().contains("overflows"));
+        assert_eq!(Ok(i64::MAX), fits);
+        assert_eq!(Some(i64::MAX), alice);
+        assert_eq!(Some(10), bob);
+    }
+
+    #[test]
+    fn test_frozen() {
+        let alice = "0".repeat(130);
+        let path = write_wallets(
+            "mockchain_test_frozen.json",
+            json!([
+                { "name": "alice", "address": alice, "balance": 100 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let unset = Wallet::list_all(&FileOps {}, &path).unwrap();
+        Wallet::set_frozen(&path, "alice", true).unwrap();
+        let blocked = Wallet::transfer(&path, "alice".to_string(), "bob".to_string(), 40);
+        let subtract = Wallet::update_balance(&path, alice.clone(), 5, "subtract");
+        let credited = Wallet::transfer(&path, "bob".to_string(), "alice".to_string(), 10);
+        let added = Wallet::update_balance(&path, alice.clone(), 5, "add");
+        let frozen_balance = balance_of(&path, "alice");
+        Wallet::set_frozen(&path, "alice", false).unwrap();
+        let restored = Wallet::transfer(&path, "alice".to_string(), "bob".to_string(), 40);
+        let missing = Wallet::set_frozen(&path, "carol", true);
+        let (after, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(unset.iter().all(|w| !w.frozen));
+        assert_eq!(Err("Wallet 'alice' is frozen".to_string()), blocked);
+        assert!(subtract.is_err());
+        assert!(credited.is_ok());
+        assert_eq!(Ok(115), added);
+        assert_eq!(Some(115), frozen_balance);
+        assert!(restored.is_ok());
+        assert!(missing.is_err());
+        assert_eq!(Some(75), after);
+        assert_eq!(Some(40), bob);
+    }
+
+    #[test]
+    fn test_transfer_insufficient_funds() {
+        let path = write_wallets(
+            "mockchain_test_transfer_insufficient.json",
+            json!([
+                { "name": "alice", "address": "0".repeat(130), "balance": 10 },
+                { "name": "bob", "address": "1".repeat(130), "balance": 10 },
+            ]),
+        );
+
+        let result = Wallet::transfer(&path, "alice".to_string(), "bob".to_string(), 40);
+        let (alice, bob) = (balance_of(&path, "alice"), balance_of(&path, "bob"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(Some(10), alice);
+        assert_eq!(Some(10), bob);
+    }
+
+    #[test]
+    fn test_transfer_unknown_recipient() {
+        let path = write_wallets(
+            "mockchain_test_transfer_unknown.json",
+            json!([{ "name": "alice", "address": "0".repeat(130), "balance": 100 }]),
+        );
+
+        let result = Wallet::transfer(&path, "alice".to_string(), "carol".to_string(), 40);
+        let alice = balance_of(&path, "alice");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Err("No wallet found under name 'carol'".to_string()),
+            result
+        );
+        assert_eq!(Some(100), alice);
+    }
 }